@@ -16,7 +16,7 @@ fn print_board(fen: &str) {
             }
         }
     }
-    println!("");
+    println!();
 }
 
 fn main() {
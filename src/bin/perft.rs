@@ -0,0 +1,345 @@
+use {
+    std::{
+        env,
+        fs,
+        process,
+        time::Instant,
+    },
+    sublime::{
+        game::position::*,
+        perft::{
+            *,
+            bench::*,
+            divide::*,
+            epd::*,
+            hashed::*,
+            parallel::*,
+        },
+    },
+};
+
+
+const USAGE: &str = "\
+Usage: perft <fen | index> <depth> [--divide] [--stats] [--time] [--threads <n>]
+             [--hash <mb>] [--verify-hash]
+       perft --suite <max depth> [--threads <n>]
+       perft --epd <file> <max depth> [--threads <n>]
+       perft --bench <max depth>
+
+  <fen | index>   FEN string, or an index into perft::FENS
+  --divide        Print the node count under each root move
+  --stats         Print every PerftCounts field, not only nodes
+  --time          Print elapsed time and nodes per second
+  --threads       Worker threads, 0 for every core (default 1)
+  --hash          Cache subtree counts in a table of this many megabytes
+  --verify-hash   Check every cache hit against an uncached perft
+  --suite         Run every EXPECTED row up to the depth and tabulate
+  --epd           Run every case of a perftsuite.epd style file up to the depth
+  --bench         Time each legal move generator on every EXPECTED row up to the depth";
+
+
+struct Options {
+    fen: String,
+    depth: u64,
+    divide: bool,
+    stats: bool,
+    time: bool,
+    threads: usize,
+    hash: usize,
+    verify_hash: bool,
+}
+
+
+fn usage_error(msg: &str) -> ! {
+    eprintln!("{}\n\n{}", msg, USAGE);
+    process::exit(2);
+}
+
+fn parse_depth(s: Option<String>) -> u64 {
+    match s.map(|s| s.parse::<u64>()) {
+        Some(Ok(depth)) => depth,
+        Some(Err(_)) => usage_error("Depth must be a non-negative integer"),
+        None => usage_error("Missing depth"),
+    }
+}
+
+fn parse_count(flag: &str, s: Option<String>) -> usize {
+    match s.map(|s| s.parse::<usize>()) {
+        Some(Ok(n)) => n,
+        _ => usage_error(&format!("{} takes a non-negative integer", flag)),
+    }
+}
+
+fn parse_fen(s: String) -> String {
+    match s.parse::<usize>() {
+        Ok(i) if i < FENS.len() => String::from(FENS[i]),
+        Ok(i) => usage_error(&format!("Index {} is out of range for {} FENS", i, FENS.len())),
+        Err(_) => s,
+    }
+}
+
+fn parse_options(args: Vec<String>) -> Options {
+    let mut positional = Vec::new();
+    let mut options = Options {
+        fen: String::new(),
+        depth: 0,
+        divide: false,
+        stats: false,
+        time: false,
+        threads: 1,
+        hash: 0,
+        verify_hash: false,
+    };
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--divide" => options.divide = true,
+            "--stats" => options.stats = true,
+            "--time" => options.time = true,
+            "--threads" => options.threads = parse_count("--threads", args.next()),
+            "--hash" => options.hash = parse_count("--hash", args.next()),
+            "--verify-hash" => options.verify_hash = true,
+            s if s.starts_with("--") => usage_error(&format!("Unknown flag {}", s)),
+            _ => positional.push(arg),
+        }
+    }
+    let mut positional = positional.into_iter();
+    options.fen = match positional.next() {
+        None => usage_error("Missing FEN or index"),
+        Some(s) => parse_fen(s),
+    };
+    options.depth = parse_depth(positional.next());
+    if positional.next().is_some() {
+        usage_error("Too many arguments; quote the FEN");
+    }
+    if options.verify_hash && options.hash == 0 {
+        usage_error("--verify-hash needs --hash");
+    }
+    if options.hash > 0 && (options.divide || options.threads != 1) {
+        usage_error("--hash is not supported with --divide or --threads");
+    }
+    options
+}
+
+
+fn print_counts(counts: &PerftCounts) {
+    for (name, value) in PerftCounts::FIELDS.iter().zip(counts.to_array()) {
+        println!("{:<18} {}", name, value);
+    }
+}
+
+fn print_time(nodes: i64, seconds: f64) {
+    println!("Time: {:.3} s", seconds);
+    println!("NPS: {:.0}", nodes as f64 / seconds);
+}
+
+fn hashed(state: &mut GameState, depth: u64, megabytes: usize, verify: bool) -> PerftCounts {
+    let mut cache = PerftCache::new(megabytes, Replacement::DepthPreferred);
+    let counts = match verify {
+        false => hashed_perft(state, depth, &mut cache),
+        true => match verified_hashed_perft(state, depth, &mut cache) {
+            Ok(counts) => counts,
+            Err(collision) => {
+                eprintln!("Hash collision: {:?}", collision);
+                process::exit(1);
+            },
+        },
+    };
+    println!("Hash hits: {} of {} probes", cache.hits, cache.probes);
+    counts
+}
+
+fn run(options: Options) {
+    let mut state = match GameState::from_fen(&options.fen) {
+        Ok(state) => state,
+        Err(e) => {
+            eprintln!("Invalid FEN {:?}: {:?}", options.fen, e);
+            process::exit(1);
+        },
+    };
+    let start = Instant::now();
+    match options.divide {
+        true => {
+            let result = divide(&mut state, options.depth);
+            let seconds = start.elapsed().as_secs_f64();
+            print_divide(&result);
+            if options.time {
+                print_time(result.iter().map(|(_, n)| n).sum(), seconds);
+            }
+        },
+        false => {
+            let counts = match (options.threads, options.hash) {
+                (1, 0) => perft(&mut state, options.depth),
+                (n, 0) => parallel_perft(&state, options.depth, n),
+                (_, mb) => hashed(&mut state, options.depth, mb, options.verify_hash),
+            };
+            let seconds = start.elapsed().as_secs_f64();
+            match options.stats {
+                true => print_counts(&counts),
+                false => println!("Nodes searched: {}", counts.nodes),
+            }
+            if options.time {
+                print_time(counts.nodes, seconds);
+            }
+        },
+    }
+}
+
+
+fn run_suite(max_depth: u64, threads: usize) {
+    println!(
+        "{:>5} {:>5} {:>16} {:>16} {:>10} {:>12}  Result",
+        "FEN", "Depth", "Expected", "Nodes", "Seconds", "NPS");
+    let mut failures = 0;
+    for (fen, depth, expected) in EXPECTED {
+        if depth > max_depth {
+            continue;
+        }
+        let index = FENS.iter().position(|f| *f == fen).unwrap();
+        let result = match PerftResult::run_parallel(fen, depth, threads) {
+            Ok(result) => result,
+            Err(e) => {
+                println!("{:>5} {:>5}  Invalid FEN: {:?}", index, depth, e);
+                failures += 1;
+                continue;
+            },
+        };
+        let pass = result.counts.matches(&expected);
+        failures += !pass as i32;
+        println!(
+            "{:>5} {:>5} {:>16} {:>16} {:>10.3} {:>12.0}  {}",
+            index,
+            result.depth,
+            expected[0],
+            result.counts.nodes,
+            result.seconds,
+            result.nps(),
+            match pass {
+                true => "pass",
+                false => "FAIL",
+            });
+        if !pass && result.counts.nodes == expected[0] {
+            // Only the detailed counts differ
+            for ((name, e), a) in PerftCounts::FIELDS.iter()
+                .zip(expected)
+                .zip(result.counts.to_array())
+            {
+                if e != -1 && e != a {
+                    println!("      {}: expected {}, got {}", name, e, a);
+                }
+            }
+        }
+    }
+    println!("{} failed", failures);
+    if failures > 0 {
+        process::exit(1);
+    }
+}
+
+
+fn run_epd_file(path: &str, max_depth: u64, threads: usize) {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("Cannot read {}: {}", path, e);
+            process::exit(1);
+        },
+    };
+    let (cases, errors) = parse_epd(&text);
+    for e in &errors {
+        println!("Line {}: {:?}", e.line, e.error);
+    }
+    println!(
+        "{:>5} {:>5} {:>16} {:>16} {:>10}  Result",
+        "Line", "Depth", "Expected", "Nodes", "Seconds");
+    let results = run_epd(&cases, max_depth, threads, |result| println!(
+        "{:>5} {:>5} {:>16} {:>16} {:>10.3}  {}",
+        result.case.line,
+        result.case.depth,
+        result.case.nodes,
+        result.nodes,
+        result.seconds,
+        match result.passed() {
+            true => "pass",
+            false => "FAIL",
+        }));
+    let failures = results.iter().filter(|r| !r.passed()).count();
+    println!(
+        "{} of {} cases failed, {} malformed lines",
+        failures, results.len(), errors.len());
+    if failures + errors.len() > 0 {
+        process::exit(1);
+    }
+}
+
+
+fn run_bench(max_depth: u64) {
+    let names: Vec<String> = GENERATORS.iter().map(|(name, _)| format!("{:>12}", name)).collect();
+    println!("{:>5} {:>5} {:>16} {}  Speedup", "FEN", "Depth", "Nodes", names.join(" "));
+    let results = bench_generators(max_depth);
+    for result in &results {
+        let index = FENS.iter().position(|f| *f == result.fen).unwrap();
+        let seconds: Vec<String> = result.seconds.iter().map(|s| format!("{:>12.3}", s)).collect();
+        println!(
+            "{:>5} {:>5} {:>16} {}  {:.2}{}",
+            index,
+            result.depth,
+            result.expected,
+            seconds.join(" "),
+            result.speedup(),
+            match result.passed() {
+                true => String::new(),
+                false => format!("  FAIL {:?}", result.nodes),
+            });
+    }
+    let totals: Vec<String> = (0..GENERATORS.len())
+        .map(|i| format!("{:>12.3}", results.iter().map(|r| r.seconds[i]).sum::<f64>()))
+        .collect();
+    println!("{:>5} {:>5} {:>16} {}", "Total", "", "", totals.join(" "));
+    if results.iter().any(|r| !r.passed()) {
+        process::exit(1);
+    }
+}
+
+
+fn parse_suite_threads(mut args: impl Iterator<Item = String>) -> usize {
+    let threads = match args.next().as_deref() {
+        None => 1,
+        Some("--threads") => parse_count("--threads", args.next()),
+        Some(s) => usage_error(&format!("Unexpected argument {}", s)),
+    };
+    if args.next().is_some() {
+        usage_error("Too many arguments");
+    }
+    threads
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(|s| s.as_str()) {
+        None | Some("-h") | Some("--help") => println!("{}", USAGE),
+        Some("--suite") => {
+            let mut args = args.into_iter().skip(1);
+            let max_depth = parse_depth(args.next());
+            run_suite(max_depth, parse_suite_threads(args));
+        },
+        Some("--bench") => {
+            let mut args = args.into_iter().skip(1);
+            let max_depth = parse_depth(args.next());
+            if args.next().is_some() {
+                usage_error("Too many arguments");
+            }
+            run_bench(max_depth);
+        },
+        Some("--epd") => {
+            let mut args = args.into_iter().skip(1);
+            let path = match args.next() {
+                Some(path) => path,
+                None => usage_error("Missing EPD file"),
+            };
+            let max_depth = parse_depth(args.next());
+            run_epd_file(&path, max_depth, parse_suite_threads(args));
+        },
+        Some(_) => run(parse_options(args)),
+    }
+}
@@ -0,0 +1,306 @@
+use {
+    std::{
+        io::{self, BufRead},
+        sync::{
+            Arc,
+            atomic::{AtomicBool, Ordering},
+        },
+        thread::{self, JoinHandle},
+        time::Duration,
+    },
+    sublime::{
+        board::color::Color::*,
+        game::{
+            board_move::*,
+            position::*,
+        },
+        perft::divide::*,
+        search::{
+            *,
+            time::*,
+            tt::*,
+        },
+    },
+};
+
+
+const NAME: &str = "sublime";
+const AUTHOR: &str = "BlakeSHerrera";
+
+const MAX_HASH_MB: usize = 4096;
+const MAX_THREADS: usize = 256;
+const MAX_MOVE_OVERHEAD_MS: u64 = 5000;
+
+
+#[derive(Debug, Default)]
+struct GoLimits {
+    depth: Option<u64>,
+    nodes: Option<u64>,
+    movetime: Option<u64>,  // Milliseconds, as are the clock fields
+    wtime: Option<u64>,
+    btime: Option<u64>,
+    winc: Option<u64>,
+    binc: Option<u64>,
+    movestogo: Option<u64>,
+    infinite: bool,
+    perft: Option<u64>,
+}
+
+struct Search {
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<Searcher>,
+}
+
+struct Engine {
+    state: GameState,
+    history: Vec<u64>,  // Zobrist hashes of the positions before state
+    searcher: Option<Searcher>,  // Lent to the search thread while it runs
+    search: Option<Search>,
+    move_overhead: Duration,
+}
+
+
+fn parse_go(tokens: &[&str]) -> GoLimits {
+    let mut limits = GoLimits::default();
+    let mut i = 0;
+    while i < tokens.len() {
+        let value = tokens.get(i + 1).and_then(|t| t.parse::<u64>().ok());
+        let field = match tokens[i] {
+            "depth" => &mut limits.depth,
+            "nodes" => &mut limits.nodes,
+            "movetime" => &mut limits.movetime,
+            "wtime" => &mut limits.wtime,
+            "btime" => &mut limits.btime,
+            "winc" => &mut limits.winc,
+            "binc" => &mut limits.binc,
+            "movestogo" => &mut limits.movestogo,
+            "perft" => &mut limits.perft,
+            "infinite" => {
+                limits.infinite = true;
+                i += 1;
+                continue;
+            },
+            _ => {
+                i += 1;
+                continue;
+            },
+        };
+        *field = value;
+        i += 2;
+    }
+    limits
+}
+
+impl GoLimits {
+
+    fn search_limits(&self, state: &GameState, overhead: Duration) -> SearchLimits {
+        // movetime is spent in full; a clock is left to the time manager
+        let (remaining, inc) = match state.turn() {
+            White => (self.wtime, self.winc.unwrap_or(0)),
+            Black => (self.btime, self.binc.unwrap_or(0)),
+        };
+        let clock = match (self.infinite, self.movetime, remaining) {
+            (false, None, Some(ms)) => Some(ClockLimits {
+                overhead,
+                ..ClockLimits::new(Duration::from_millis(ms), Duration::from_millis(inc), self.movestogo)
+            }),
+            _ => None,
+        };
+        SearchLimits {
+            depth: self.depth,
+            nodes: self.nodes,
+            time: self.movetime.filter(|_| !self.infinite).map(Duration::from_millis),
+            clock,
+        }
+    }
+}
+
+fn uci_score(score: Score) -> String {
+    match score {
+        Score::Centipawns(cp) => format!("cp {}", cp),
+        Score::Mate(moves) => format!("mate {}", moves),
+    }
+}
+
+fn print_info(info: &SearchInfo) {
+    let pv: Vec<String> = info.pv.iter().map(|mv| mv.pacn()).collect();
+    println!(
+        "info depth {} score {} nodes {} nps {} hashfull {} time {} pv {}",
+        info.depth,
+        uci_score(info.score()),
+        info.nodes,
+        info.nps(),
+        info.hashfull,
+        info.elapsed.as_millis(),
+        pv.join(" "),
+    );
+}
+
+fn think(
+    mut searcher: Searcher,
+    state: GameState,
+    history: Vec<u64>,
+    limits: GoLimits,
+    overhead: Duration,
+    stop: Arc<AtomicBool>
+) -> Searcher {
+    let info = searcher.search(&state, &history, limits.search_limits(&state, overhead), print_info);
+    // UCI forbids bestmove before stop when searching infinitely
+    while limits.infinite && !stop.load(Ordering::Relaxed) {
+        thread::sleep(Duration::from_millis(1));
+    }
+    match info.best_move() {
+        Some(mv) => println!("bestmove {}", mv.pacn()),
+        None => println!("bestmove 0000"),
+    }
+    searcher
+}
+
+impl Engine {
+
+    fn new() -> Engine {
+        Engine {
+            state: GameState::from_fen(START_FEN).unwrap(),
+            history: Vec::new(),
+            searcher: Some(Searcher::new()),
+            search: None,
+            move_overhead: DEFAULT_MOVE_OVERHEAD,
+        }
+    }
+
+    fn stop(&mut self) {
+        if let Some(search) = self.search.take() {
+            search.stop.store(true, Ordering::Relaxed);
+            self.searcher = Some(search.handle.join().unwrap());
+        }
+    }
+
+    fn position(&mut self, tokens: &[&str]) {
+        let moves_at = tokens.iter().position(|&t| t == "moves").unwrap_or(tokens.len());
+        let fen = match tokens.first() {
+            Some(&"startpos") => String::from(START_FEN),
+            Some(&"fen") => tokens[1..moves_at].join(" "),
+            _ => return println!("info string position needs startpos or fen"),
+        };
+        let mut state = match GameState::from_fen(&fen) {
+            Ok(state) => state,
+            Err(e) => return println!("info string invalid fen {:?}", e),
+        };
+        let mut history = Vec::new();
+        for s in tokens.iter().skip(moves_at + 1) {
+            match Move::from_str(&state, s) {
+                Ok(mv) => {
+                    history.push(state.zobrist_hash);
+                    state.push(mv);
+                },
+                Err(e) => return println!("info string invalid move {} {:?}", s, e),
+            }
+        }
+        self.state = state;
+        self.history = history;
+    }
+
+    fn go(&mut self, tokens: &[&str]) {
+        self.stop();
+        let limits = parse_go(tokens);
+        let searcher = self.searcher.take().unwrap();
+        let stop = searcher.stop_flag();
+        stop.store(false, Ordering::Relaxed);
+        let (state, history, thread_stop) = (self.state.clone(), self.history.clone(), stop.clone());
+        let overhead = self.move_overhead;
+        // perft runs on the search thread too, so that stop and isready are still read
+        let handle = match limits.perft {
+            Some(depth) => thread::spawn(move || {
+                match divide_until(&mut state.clone(), depth, &thread_stop) {
+                    Some(divide) => print_divide(&divide),
+                    None => println!("info string perft stopped"),
+                }
+                searcher
+            }),
+            None => thread::spawn(move || think(searcher, state, history, limits, overhead, thread_stop)),
+        };
+        self.search = Some(Search { stop, handle });
+    }
+
+    fn searcher(&mut self) -> &mut Searcher {
+        // Waits for any running search to hand the searcher back
+        self.stop();
+        self.searcher.as_mut().unwrap()
+    }
+
+    fn print_options() {
+        println!("option name Hash type spin default {} min 1 max {}",
+            TranspositionTable::DEFAULT_MB, MAX_HASH_MB);
+        println!("option name Clear Hash type button");
+        println!("option name Threads type spin default 1 min 1 max {}", MAX_THREADS);
+        println!("option name Move Overhead type spin default {} min 0 max {}",
+            DEFAULT_MOVE_OVERHEAD.as_millis(), MAX_MOVE_OVERHEAD_MS);
+    }
+
+    fn set_option(&mut self, tokens: &[&str]) {
+        // setoption name <id> [value <x>]
+        let value_at = tokens.iter().position(|&t| t == "value").unwrap_or(tokens.len());
+        let name = tokens.get(1..value_at).unwrap_or_default().join(" ");
+        let value = tokens.get(value_at + 1..).unwrap_or_default().join(" ");
+        match name.to_lowercase().as_str() {
+            "hash" => match value.parse::<usize>() {
+                Ok(mb) if (1..=MAX_HASH_MB).contains(&mb) => self.searcher().set_hash_size(mb),
+                _ => println!("info string Hash takes 1 to {} MB", MAX_HASH_MB),
+            },
+            "clear hash" => self.searcher().clear_hash(),
+            "threads" => match value.parse::<usize>() {
+                Ok(n) if (1..=MAX_THREADS).contains(&n) => self.searcher().set_threads(n),
+                _ => println!("info string Threads takes 1 to {}", MAX_THREADS),
+            },
+            "move overhead" => match value.parse::<u64>() {
+                Ok(ms) if ms <= MAX_MOVE_OVERHEAD_MS => self.move_overhead = Duration::from_millis(ms),
+                _ => println!("info string Move Overhead takes 0 to {} ms", MAX_MOVE_OVERHEAD_MS),
+            },
+            _ => println!("info string unknown option {}", name),
+        }
+    }
+
+    fn handle(&mut self, line: &str) -> bool {
+        // Returns false on quit
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let Some((&command, args)) = tokens.split_first() else {
+            return true;
+        };
+        match command {
+            "uci" => {
+                println!("id name {}", NAME);
+                println!("id author {}", AUTHOR);
+                Engine::print_options();
+                println!("uciok");
+            },
+            "isready" => println!("readyok"),
+            "ucinewgame" => {
+                self.searcher().clear_hash();
+                self.state = GameState::from_fen(START_FEN).unwrap();
+                self.history.clear();
+            },
+            "position" => {
+                self.stop();
+                self.position(args);
+            },
+            "go" => self.go(args),
+            "stop" => self.stop(),
+            "setoption" => self.set_option(args),
+            "quit" => return false,
+            _ => println!("info string unknown command {}", command),
+        }
+        true
+    }
+}
+
+
+fn main() {
+    let mut engine = Engine::new();
+    for line in io::stdin().lock().lines() {
+        match line {
+            Ok(line) if engine.handle(&line) => (),
+            _ => break,
+        }
+    }
+    engine.stop();
+}
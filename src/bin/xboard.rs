@@ -0,0 +1,338 @@
+// Chess Engine Communication Protocol, version 2
+// https://www.gnu.org/software/xboard/engine-intf.html
+
+use {
+    std::{
+        io::{self, BufRead},
+        sync::{
+            Arc,
+            atomic::{AtomicBool, Ordering},
+            mpsc::{self, Sender},
+        },
+        thread::{self, JoinHandle},
+        time::Duration,
+    },
+    sublime::{
+        board::color::{*, Color::*},
+        game::{
+            board_move::*,
+            history::*,
+        },
+        search::{
+            *,
+            time::*,
+        },
+    },
+};
+
+
+const NAME: &str = "sublime";
+
+const FEATURES: [&str; 10] = [
+    "ping=1", "setboard=1", "playother=1", "usermove=1", "san=0",
+    "sigint=0", "sigterm=0", "colors=0", "analyze=0", "smp=1",
+];
+
+
+#[derive(Debug, Default)]
+struct TimeControl {
+    moves_per_session: u64,  // 0 for the whole game
+    base_ms: u64,
+    increment_ms: u64,
+    move_time_ms: Option<u64>,  // st
+    depth: Option<u64>,  // sd
+    engine_clock_ms: Option<u64>,  // time
+}
+
+enum Event {
+    Line(String),
+    SearchDone(u64),  // With the id of the search
+}
+
+struct Search {
+    id: u64,
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<(Searcher, Option<Move>)>,
+}
+
+struct Engine {
+    game: Game,
+    searcher: Option<Searcher>,  // Lent to the search thread while it runs
+    search: Option<Search>,
+    searches: u64,  // Started so far, to tell a finished search from an abandoned one
+    events: Sender<Event>,  // For the search thread to wake the main loop when done
+    engine_color: Option<Color>,  // None in force mode
+    time_control: TimeControl,
+    post: bool,
+}
+
+
+fn parse_minutes(s: &str) -> Option<u64> {
+    // level takes minutes or minutes:seconds
+    match s.split_once(':') {
+        Some((m, sec)) => Some(m.parse::<u64>().ok()? * 60_000 + sec.parse::<u64>().ok()? * 1000),
+        None => Some(s.parse::<u64>().ok()? * 60_000),
+    }
+}
+
+fn parse_seconds(s: &str) -> Option<u64> {
+    s.parse::<f64>().ok().map(|sec| (sec * 1000.0) as u64)
+}
+
+fn xboard_score(score: Score) -> i32 {
+    // Mates are reported as 100000 + moves, negated when being mated
+    match score {
+        Score::Centipawns(cp) => cp,
+        Score::Mate(moves) if moves > 0 => 100_000 + moves,
+        Score::Mate(moves) => -100_000 + moves,
+    }
+}
+
+fn result_string(result: GameResult, termination: Termination) -> String {
+    let score = match result {
+        GameResult::Win(White) => "1-0",
+        GameResult::Win(Black) => "0-1",
+        GameResult::Draw => "1/2-1/2",
+    };
+    let reason = match (result, termination) {
+        (GameResult::Win(White), _) => "White mates",
+        (GameResult::Win(Black), _) => "Black mates",
+        (_, Termination::Stalemate) => "Stalemate",
+        (_, Termination::InsufficientMaterial) => "Insufficient material",
+        (_, Termination::FivefoldRepetition | Termination::ThreefoldRepetition) => "Draw by repetition",
+        _ => "Draw by move rule",
+    };
+    format!("{} {{{}}}", score, reason)
+}
+
+impl Engine {
+
+    fn new(events: Sender<Event>) -> Engine {
+        Engine {
+            game: Game::new(),
+            searcher: Some(Searcher::new()),
+            search: None,
+            searches: 0,
+            events,
+            engine_color: Some(Black),
+            time_control: TimeControl::default(),
+            post: false,
+        }
+    }
+
+    fn report_outcome(&self) -> bool {
+        // Returns true when the game is over
+        match self.game.outcome() {
+            Some((result, termination)) => {
+                println!("{}", result_string(result, termination));
+                true
+            },
+            None => false,
+        }
+    }
+
+    fn search_limits(&self) -> SearchLimits {
+        // st is spent in full; a clock is left to the time manager
+        let tc = &self.time_control;
+        // The engine is on move, so it has made one move for each full move before this one,
+        // whichever side it plays and whichever side moved first
+        let played = self.game.state().fullmove_ctr().saturating_sub(1) as u64;
+        let moves_to_go = match tc.moves_per_session {
+            0 => None,
+            mps => Some(mps - played % mps),
+        };
+        let clock = match (tc.move_time_ms, tc.engine_clock_ms.unwrap_or(tc.base_ms)) {
+            (None, ms) if ms > 0 => Some(ClockLimits::new(
+                Duration::from_millis(ms),
+                Duration::from_millis(tc.increment_ms),
+                moves_to_go,
+            )),
+            _ => None,
+        };
+        // With none of these the search runs until ? or another command stops it
+        SearchLimits {
+            depth: tc.depth,
+            nodes: None,
+            time: tc.move_time_ms.map(Duration::from_millis),
+            clock,
+        }
+    }
+
+    fn think(&mut self) {
+        // Searches on its own thread so that commands are still read
+        let mut searcher = self.searcher.take().unwrap();
+        let stop = searcher.stop_flag();
+        stop.store(false, Ordering::Relaxed);
+        let post = self.post;
+        let state = self.game.state().clone();
+        let history = self.game.hashes()[..self.game.hashes().len() - 1].to_vec();
+        let limits = self.search_limits();
+        let events = self.events.clone();
+        self.searches += 1;
+        let id = self.searches;
+        let handle = thread::spawn(move || {
+            let info = searcher.search(&state, &history, limits, |info| {
+                if post {
+                    // ply score time(centiseconds) nodes pv
+                    let pv: Vec<String> = info.pv.iter().map(|mv| mv.pacn()).collect();
+                    println!("{} {} {} {} {}", info.depth, xboard_score(info.score()),
+                        info.elapsed.as_millis() / 10, info.nodes, pv.join(" "));
+                }
+            });
+            _ = events.send(Event::SearchDone(id));
+            (searcher, info.best_move())
+        });
+        self.search = Some(Search { id, stop, handle });
+    }
+
+    fn finish_search(&mut self, play: bool) {
+        // Stops any running search, and plays its move if asked to
+        let Some(search) = self.search.take() else {
+            return;
+        };
+        search.stop.store(true, Ordering::Relaxed);
+        let (searcher, best) = search.handle.join().unwrap();
+        self.searcher = Some(searcher);
+        if let Some(best) = best && play {
+            self.game.push(best).unwrap();
+            println!("move {}", best.pacn());
+            self.report_outcome();
+        }
+    }
+
+    fn searcher(&mut self) -> &mut Searcher {
+        // Abandons any running search to get the searcher back
+        self.finish_search(false);
+        self.searcher.as_mut().unwrap()
+    }
+
+    fn think_if_on_move(&mut self) {
+        if self.engine_color == Some(self.game.state().turn()) && !self.game.is_over() {
+            self.think();
+        }
+    }
+
+    fn user_move(&mut self, s: &str) {
+        let mv = match Move::from_str(self.game.state(), s) {
+            Ok(mv) => mv,
+            Err(_) => return println!("Illegal move: {}", s),
+        };
+        if let Err(e) = self.game.push(mv) {
+            return println!("Illegal move ({:?}): {}", e, s);
+        }
+        if !self.report_outcome() {
+            self.think_if_on_move();
+        }
+    }
+
+    fn time_command(&mut self, command: &str, args: &[&str]) -> Option<()> {
+        let tc = &mut self.time_control;
+        let arg = args.first()?;
+        match command {
+            // level <moves per session> <base minutes[:seconds]> <increment seconds>
+            "level" => {
+                let [mps, base, inc] = args else {
+                    return None;
+                };
+                tc.moves_per_session = mps.parse().ok()?;
+                tc.base_ms = parse_minutes(base)?;
+                tc.increment_ms = parse_seconds(inc)?;
+                tc.move_time_ms = None;
+            },
+            "st" => tc.move_time_ms = Some(parse_seconds(arg)?),
+            "sd" => tc.depth = Some(arg.parse().ok()?),
+            // Clocks are in centiseconds
+            "time" => tc.engine_clock_ms = Some(arg.parse::<u64>().ok()? * 10),
+            // The opponent's clock plays no part in time allocation
+            "otim" => _ = arg.parse::<u64>().ok()?,
+            _ => return None,
+        }
+        Some(())
+    }
+
+    fn handle(&mut self, line: &str) -> bool {
+        // Returns false on quit
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let Some((&command, args)) = tokens.split_first() else {
+            return true;
+        };
+        let arg = args.first().copied().unwrap_or_default();
+        // Commands that change the game abandon the search, which has not moved yet
+        if matches!(command, "new" | "setboard" | "usermove" | "go" | "playother" | "force" | "result"
+            | "undo" | "remove" | "quit") {
+            self.finish_search(false);
+        }
+        match command {
+            "xboard" | "accepted" | "rejected" | "random" | "hard" | "easy" | "computer" => (),
+            "protover" => {
+                println!("feature myname=\"{}\" {}", NAME, FEATURES.join(" "));
+                println!("feature done=1");
+            },
+            "ping" => println!("pong {}", arg),
+            // Move now
+            "?" => self.finish_search(true),
+            "new" => {
+                self.game = Game::new();
+                self.searcher().clear_hash();
+                self.engine_color = Some(Black);
+                self.time_control.depth = None;
+            },
+            "setboard" => match Game::from_fen(&args.join(" ")) {
+                Ok(game) => self.game = game,
+                Err(e) => println!("tellusererror Illegal position: {:?}", e),
+            },
+            "usermove" => self.user_move(arg),
+            "go" => {
+                self.engine_color = Some(self.game.state().turn());
+                self.think_if_on_move();
+            },
+            "playother" => self.engine_color = Some(self.game.state().turn().inv()),
+            "force" | "result" => self.engine_color = None,
+            "undo" => {
+                self.game.pop();
+            },
+            "remove" => {
+                self.game.pop();
+                self.game.pop();
+            },
+            "level" | "st" | "sd" | "time" | "otim" => if self.time_command(command, args).is_none() {
+                println!("Error (invalid arguments): {}", line);
+            },
+            "cores" => match arg.parse::<usize>() {
+                Ok(n) if n > 0 => self.searcher().set_threads(n),
+                _ => println!("Error (invalid arguments): {}", line),
+            },
+            "post" => self.post = true,
+            "nopost" => self.post = false,
+            "quit" => return false,
+            _ => println!("Error (unknown command): {}", command),
+        }
+        true
+    }
+}
+
+
+fn main() {
+    // Input and finished searches arrive on one channel, in order
+    let (events, received) = mpsc::channel();
+    let mut engine = Engine::new(events.clone());
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines() {
+            match line {
+                Ok(line) => _ = events.send(Event::Line(line)),
+                Err(_) => break,
+            }
+        }
+        _ = events.send(Event::Line(String::from("quit")));
+    });
+    for event in received {
+        match event {
+            Event::Line(line) if engine.handle(&line) => (),
+            Event::Line(_) => break,
+            // Already collected if a command stopped the search first
+            Event::SearchDone(id) if engine.search.as_ref().is_some_and(|s| s.id == id) => engine.finish_search(true),
+            Event::SearchDone(_) => (),
+        }
+    }
+    engine.finish_search(false);
+}
@@ -26,11 +26,15 @@ impl Direction {
         Southeast, South, Southwest, West
     ];
 
+    pub const fn inv(self) -> Direction {
+        Direction::ALL[(self as usize + 4) % 8]
+    }
+
     pub const fn shift(self, mask: u64, n: usize) -> u64 {
         match self {
-            North => (mask & !TOP_RANKS[n]) << 8 * n,
+            North => (mask & !TOP_RANKS[n]) << (8 * n),
             East => (mask & !RIGHT_FILES[n]) << n,
-            South => (mask & !BOTTOM_RANKS[n]) >> 8 * n,
+            South => (mask & !BOTTOM_RANKS[n]) >> (8 * n),
             West => (mask & !LEFT_FILES[n]) >> n,
             Northwest => North.shift(West.shift(mask, n), n),
             Northeast => North.shift(East.shift(mask, n), n),
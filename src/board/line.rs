@@ -133,9 +133,6 @@ impl Rank {
     ];
 
     pub const fn is_promotion_rank(self) -> bool {
-        match self {
-            Rank1 | Rank8 => true,
-            _ => false,
-        }
+        matches!(self, Rank1 | Rank8)
     }
 }
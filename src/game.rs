@@ -1,3 +1,7 @@
 pub mod board_move;
+pub mod history;
+pub mod legal_gen;
 pub mod move_gen;
 pub mod position;
+pub mod see;
+pub mod staged_gen;
@@ -18,7 +18,7 @@ use {
 };
 
 
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum IllegalMove {
     InCheck,
     InvalidMove,
@@ -43,12 +43,21 @@ const EP_CAPTURE_OFFSET: u64 = EP_FILE_OFFSET + File::BITS;
 const IS_CASTLING_OFFSET: u64 = EP_CAPTURE_OFFSET + 1;
 const CASTLING_OPTION_OFFSET: u64 = IS_CASTLING_OFFSET + 1;
 
+#[allow(dead_code)]
 const BITS_TO_SPARE: u64 = 64 - CASTLING_OPTION_OFFSET - 2;
 
 
-#[derive(Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub struct Move(pub u64);
 
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Undo {
+    // State that push discards and pop_partial cannot recover from the move
+    pub fen_info: u32,
+    pub zobrist_hash: u64,
+}
+
 impl Move {
 
     pub const fn new(
@@ -67,37 +76,40 @@ impl Move {
             } << ORIGIN_PIECE_OFFSET
             
             | (destination as u64) << DESTINATION_SQUARE_OFFSET
-            | match promotion {
-                None => Piece::EMPTY_CODE,
-                Some(p) => p.as_piece(board.turn()) as u64,
+            | match (promotion, moved) {
+                (Some(p), _) => p.as_piece(board.turn()) as u64,
+                (None, Some(p)) => p as u64,
+                (None, None) => Piece::EMPTY_CODE,
             } << DESTINATION_PIECE_OFFSET;
         
         match [moved, captured] {
             [Some(p), None] => match p.as_generic() {
                 Pawn => match origin.file() as u8 == destination.file() as u8 {
-                    true => // En passant
+                    false => // En passant
                         mv |= (p.inv() as u64) << CAPTURED_PIECE_OFFSET
                             | match p.color() {
                                 White => destination as u64 - 8,
                                 Black => destination as u64 + 8,
                             } << CAPTURED_SQUARE_OFFSET
                             | 1 << EP_CAPTURE_OFFSET,
-                    false => match [origin.rank(), destination.rank()] {
-                        [Rank2, Rank4] | [Rank7, Rank5] => 
+                    true => {
+                        mv |= Piece::EMPTY_CODE << CAPTURED_PIECE_OFFSET;
+                        if let [Rank2, Rank4] | [Rank7, Rank5] = [origin.rank(), destination.rank()] {
                             mv |= 1 << DOUBLE_PAWN_PUSH_OFFSET
-                                | (origin.file() as u64) << EP_FILE_OFFSET,
-                        _ => (),
-                    }
-                },
-                King => match (origin.file(), destination.file()) {
-                    // TODO maybe make a generic castling
-                    (EFile, GFile) => mv |= 1 << IS_CASTLING_OFFSET
-                        | (Kingside.to_quadrant(board.turn()) as u64) << CASTLING_OPTION_OFFSET,
-                    (EFile, CFile) => mv |= 1 << IS_CASTLING_OFFSET
-                        | (Queenside.to_quadrant(board.turn()) as u64) << CASTLING_OPTION_OFFSET,
-                    _ => (),
+                                | (origin.file() as u64) << EP_FILE_OFFSET;
+                        }
+                    },
                 },
-                _ => (),
+                King => mv |= Piece::EMPTY_CODE << CAPTURED_PIECE_OFFSET
+                    | match (origin.file(), destination.file()) {
+                        // TODO maybe make a generic castling
+                        (EFile, GFile) => 1 << IS_CASTLING_OFFSET
+                            | (Kingside.to_quadrant(p.color()) as u64) << CASTLING_OPTION_OFFSET,
+                        (EFile, CFile) => 1 << IS_CASTLING_OFFSET
+                            | (Queenside.to_quadrant(p.color()) as u64) << CASTLING_OPTION_OFFSET,
+                        _ => 0,
+                    },
+                _ => mv |= Piece::EMPTY_CODE << CAPTURED_PIECE_OFFSET,
             },
             [Some(_), Some(p)] => 
                 // Regular capture
@@ -131,6 +143,18 @@ impl Move {
         Piece::ALL[(self.0 >> DESTINATION_PIECE_OFFSET & Piece::BIT_MAX) as usize]
     }
 
+    pub const fn is_promotion(self) -> bool {
+        self.0 >> ORIGIN_PIECE_OFFSET & Piece::BIT_MAX 
+            != self.0 >> DESTINATION_PIECE_OFFSET & Piece::BIT_MAX
+    }
+
+    pub const fn promotion(self) -> Option<Promotion> {
+        match self.is_promotion() {
+            true => Some(Promotion::ALL[self.destination_piece().as_generic() as usize]),
+            false => None,
+        }
+    }
+
     pub const fn is_capture(self) -> bool {
         self.0 >> CAPTURED_PIECE_OFFSET & Piece::BIT_MAX != Piece::EMPTY_CODE
     }
@@ -161,6 +185,15 @@ impl Move {
     pub const fn get_castling(self) -> Quadrant {
         Quadrant::ALL[(self.0 >> CASTLING_OPTION_OFFSET & 0b11) as usize]
     }
+
+    pub const fn compact(self) -> u16 {
+        // Origin, destination and promotion: enough to tell apart the moves of a position
+        let promotion = match self.promotion() {
+            Some(p) => p as u16 + 1,
+            None => 0,
+        };
+        self.origin_square() as u16 | (self.destination_square() as u16) << 6 | promotion << 12
+    }
 }
 
 
@@ -169,33 +202,43 @@ impl GameState {
     pub const fn push_partial(&mut self, mv: Move) {
         // No fen info updates; to check for move legality
         self.remove_piece(mv.origin_piece(), mv.origin_square());
-        match mv.captured_piece() {
-            Some(p) => self.remove_piece(p, mv.captured_square()),
-            None => (),
-        };
+        if let Some(p) = mv.captured_piece() {
+            self.remove_piece(p, mv.captured_square());
+        }
         self.put_piece(mv.destination_piece(), mv.destination_square());
 
-        match mv.is_castling() {
-            true => {
-                let q = mv.get_castling();
-                self.remove_piece(q.rook(), q.rook_start());
-                self.put_piece(q.rook(), q.rook_end());
-            },
-            false => (),
+        if mv.is_castling() {
+            let q = mv.get_castling();
+            self.remove_piece(q.rook(), q.rook_start());
+            self.put_piece(q.rook(), q.rook_end());
         }
     }
 
-    pub const fn push(&mut self, mv: Move) {
+    const fn deny_castling_from(&mut self, square: Square) {
+        // A rook moving from or being captured on its corner loses its rights
+        match square {
+            H1 => self.deny_castling(WhiteKingside),
+            A1 => self.deny_castling(WhiteQueenside),
+            H8 => self.deny_castling(BlackKingside),
+            A8 => self.deny_castling(BlackQueenside),
+            _ => (),
+        }
+    }
+
+    pub const fn push(&mut self, mv: Move) -> Undo {
+        let undo = Undo {
+            fen_info: self.fen_info,
+            zobrist_hash: self.zobrist_hash,
+        };
         self.push_partial(mv);
         
         self.deny_ep();
         match mv.origin_piece().as_generic() {
             Pawn => {
                 self.reset_halfmove_ctr();
-                match mv.is_double_pawn_push() {
-                    true => self.set_ep_target(mv.origin_square().file()),
-                    false => (),
-                };
+                if mv.is_double_pawn_push() {
+                    self.set_ep_target(mv.origin_square().file());
+                }
             },
             King => {
                 let color = self.turn();
@@ -205,34 +248,53 @@ impl GameState {
             },
             _ => self.inc_halfmove_ctr(),
         };
+        if mv.is_capture() {
+            self.reset_halfmove_ctr();
+        }
         
-        match [mv.origin_square(), mv.destination_square()] {
-            [H1, _] | [_, H1] => self.deny_castling(WhiteKingside),
-            [A1, _] | [_, A1] => self.deny_castling(WhiteQueenside),
-            [H8, _] | [_, H8] => self.deny_castling(BlackKingside),
-            [A8, _] | [_, A8] => self.deny_castling(BlackQueenside),
-            _ => (),
-        };
+        self.deny_castling_from(mv.origin_square());
+        self.deny_castling_from(mv.destination_square());
 
         self.inc_turn();
+        undo
     }
 
     pub const fn pop_partial(&mut self, mv: Move) {
         // No fen info updates
         self.put_piece(mv.origin_piece(), mv.origin_square());
         self.remove_piece(mv.destination_piece(), mv.destination_square());
-        match mv.captured_piece() {
-            None => (),
-            Some(p) => self.put_piece(p, mv.captured_square()),
+        if let Some(p) = mv.captured_piece() {
+            self.put_piece(p, mv.captured_square());
         }
-        match mv.is_castling() {
-            false => (),
-            true => {
-                let q = mv.get_castling();
-                self.put_piece(q.rook(), q.rook_start());
-                self.remove_piece(q.rook(), q.rook_end());
-            }
+        if mv.is_castling() {
+            let q = mv.get_castling();
+            self.put_piece(q.rook(), q.rook_start());
+            self.remove_piece(q.rook(), q.rook_end());
         }
         // It is the caller's responsibility to reset the last fen_info.
     }
+
+    pub const fn pop(&mut self, mv: Move, undo: Undo) {
+        // Exact inverse of the push that returned undo
+        self.pop_partial(mv);
+        self.fen_info = undo.fen_info;
+        self.zobrist_hash = undo.zobrist_hash;
+    }
+
+    pub const fn push_null(&mut self) -> Undo {
+        // Passes the turn without moving, for null move pruning
+        let undo = Undo {
+            fen_info: self.fen_info,
+            zobrist_hash: self.zobrist_hash,
+        };
+        self.deny_ep();
+        self.inc_halfmove_ctr();
+        self.inc_turn();
+        undo
+    }
+
+    pub const fn pop_null(&mut self, undo: Undo) {
+        self.fen_info = undo.fen_info;
+        self.zobrist_hash = undo.zobrist_hash;
+    }
 }
@@ -0,0 +1,220 @@
+// A game as a sequence of moves from a starting position, with the
+// history needed for repetition and the automatic and claimable draws.
+// https://handbook.fide.com/chapter/E012023 articles 5 and 9
+
+use {
+    crate::{
+        board::{
+            color::{*, Color::*},
+            piece::GenericPiece::*,
+        },
+        hashing::bitmask,
+        parse::fen::FenError,
+    },
+    super::{
+        board_move::*,
+        move_gen::*,
+        position::*,
+    },
+};
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameResult {
+    Win(Color),
+    Draw,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Termination {
+    Checkmate,
+    Stalemate,
+    InsufficientMaterial,
+    FivefoldRepetition,
+    SeventyFiveMoveRule,
+    // Only end the game when claimed
+    ThreefoldRepetition,
+    FiftyMoveRule,
+}
+
+#[derive(Debug)]
+pub enum GameError {
+    GameOver(GameResult, Termination),
+    IllegalMove(IllegalMove),
+    NoDrawToClaim,
+}
+
+
+impl GameState {
+
+    pub const fn is_75_move_rule(&self) -> bool {
+        self.halfmove_ctr() >= 150
+    }
+
+    pub const fn is_insufficient_material(&self) -> bool {
+        // Neither side can mate: bare kings, a single minor piece,
+        // or only bishops that all stand on one colour of square
+        let heavy = self.bitboard[Rook.as_color(White) as usize]
+            | self.bitboard[Rook.as_color(Black) as usize]
+            | self.bitboard[Queen.as_color(White) as usize]
+            | self.bitboard[Queen.as_color(Black) as usize]
+            | self.bitboard[Pawn.as_color(White) as usize]
+            | self.bitboard[Pawn.as_color(Black) as usize];
+        let knights = self.bitboard[Knight.as_color(White) as usize]
+            | self.bitboard[Knight.as_color(Black) as usize];
+        let bishops = self.bitboard[Bishop.as_color(White) as usize]
+            | self.bitboard[Bishop.as_color(Black) as usize];
+        heavy == 0 && match knights {
+            0 => bishops & bitmask::LIGHT_SQUARES == 0 || bishops & bitmask::DARK_SQUARES == 0,
+            _ => bishops == 0 && knights.count_ones() == 1,
+        }
+    }
+}
+
+
+pub struct Game {
+    state: GameState,
+    moves: Vec<Move>,
+    undos: Vec<Undo>,
+    // Zobrist hash of every position reached, starting position first
+    hashes: Vec<u64>,
+    outcome: Option<(GameResult, Termination)>,
+}
+
+impl Game {
+
+    pub fn new() -> Game {
+        Game::from_state(GameState::from_fen(START_FEN).unwrap())
+    }
+
+    pub fn from_fen(fen: &str) -> Result<Game, FenError> {
+        Ok(Game::from_state(GameState::from_fen(fen)?))
+    }
+
+    pub fn from_state(state: GameState) -> Game {
+        let mut game = Game {
+            hashes: vec![state.zobrist_hash],
+            state,
+            moves: Vec::new(),
+            undos: Vec::new(),
+            outcome: None,
+        };
+        game.outcome = game.adjudicate();
+        game
+    }
+
+    pub const fn state(&self) -> &GameState {
+        &self.state
+    }
+
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    pub fn hashes(&self) -> &[u64] {
+        // Starting position first, current position last
+        &self.hashes
+    }
+
+    pub fn legal_moves(&mut self) -> Vec<Move> {
+        let mut moves = [Move(0); MAX_LEGAL_MOVES];
+        let n = self.state.generate_legal_moves(&mut moves);
+        moves[..n].to_vec()
+    }
+
+    pub const fn outcome(&self) -> Option<(GameResult, Termination)> {
+        self.outcome
+    }
+
+    pub const fn is_over(&self) -> bool {
+        self.outcome.is_some()
+    }
+
+    pub fn repetitions(&self) -> usize {
+        // Times the current position has occurred, this one included.
+        // Only positions since the last capture or pawn move can repeat.
+        let plies = (self.state.halfmove_ctr() as usize).min(self.hashes.len() - 1);
+        self.hashes.iter()
+            .rev()
+            .take(plies + 1)
+            .step_by(2)
+            .filter(|&&h| h == self.state.zobrist_hash)
+            .count()
+    }
+
+    fn adjudicate(&mut self) -> Option<(GameResult, Termination)> {
+        // Automatic terminations only; claimable draws need claim_draw
+        let side = self.state.turn();
+        if self.legal_moves().is_empty() {
+            return match self.state.in_check(side) {
+                true => Some((GameResult::Win(side.inv()), Termination::Checkmate)),
+                false => Some((GameResult::Draw, Termination::Stalemate)),
+            };
+        }
+        if self.repetitions() >= 5 {
+            Some((GameResult::Draw, Termination::FivefoldRepetition))
+        } else if self.state.is_75_move_rule() {
+            Some((GameResult::Draw, Termination::SeventyFiveMoveRule))
+        } else if self.state.is_insufficient_material() {
+            Some((GameResult::Draw, Termination::InsufficientMaterial))
+        } else {
+            None
+        }
+    }
+
+    pub fn claimable_draw(&self) -> Option<Termination> {
+        if self.repetitions() >= 3 {
+            Some(Termination::ThreefoldRepetition)
+        } else if self.state.is_50_move_rule() {
+            Some(Termination::FiftyMoveRule)
+        } else {
+            None
+        }
+    }
+
+    pub fn claim_draw(&mut self) -> Result<Termination, GameError> {
+        if let Some((result, termination)) = self.outcome {
+            return Err(GameError::GameOver(result, termination));
+        }
+        match self.claimable_draw() {
+            None => Err(GameError::NoDrawToClaim),
+            Some(termination) => {
+                self.outcome = Some((GameResult::Draw, termination));
+                Ok(termination)
+            },
+        }
+    }
+
+    pub fn push(&mut self, mv: Move) -> Result<(), GameError> {
+        if let Some((result, termination)) = self.outcome {
+            return Err(GameError::GameOver(result, termination));
+        }
+        if !self.legal_moves().contains(&mv) {
+            let reason = self.state
+                .try_move(mv.origin_square(), mv.destination_square(), mv.promotion())
+                .err()
+                .unwrap_or(IllegalMove::InvalidMove);
+            return Err(GameError::IllegalMove(reason));
+        }
+        self.undos.push(self.state.push(mv));
+        self.moves.push(mv);
+        self.hashes.push(self.state.zobrist_hash);
+        self.outcome = self.adjudicate();
+        Ok(())
+    }
+
+    pub fn pop(&mut self) -> Option<Move> {
+        // Takes back the last move, and with it any result or claimed draw
+        let mv = self.moves.pop()?;
+        self.state.pop(mv, self.undos.pop().unwrap());
+        self.hashes.pop();
+        self.outcome = self.adjudicate();
+        Some(mv)
+    }
+}
+
+impl Default for Game {
+    fn default() -> Game {
+        Game::new()
+    }
+}
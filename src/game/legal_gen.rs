@@ -0,0 +1,174 @@
+// Legal move generation from checkers, pins and the enemy attack map,
+// without making and unmaking each pseudo-legal move
+// https://www.chessprogramming.org/Pin
+// https://www.chessprogramming.org/Check
+
+use {
+    crate::{
+        board::{
+            color::*,
+            piece::{*, GenericPiece::*},
+            square::*,
+            zone::*,
+        },
+        hashing::{
+            bitmask::*,
+            magic::*,
+        },
+    },
+    super::{
+        board_move::*,
+        move_gen::*,
+        position::*,
+    },
+};
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Legality {
+    // Computed once per position for the side to move
+    pub king: Square,
+    pub checkers: u64,
+    pub pinned: u64,
+    // Squares attacked by the enemy, seen through the king so it cannot step back along a check
+    pub attacked: u64,
+    // Destinations that resolve a check for pieces other than the king
+    pub check_mask: u64,
+}
+
+
+impl GameState {
+
+    pub fn legality(&self) -> Legality {
+        let color = self.turn();
+        let enemy = color.inv();
+        let king = Square::ALL[self.bitboard[King.as_color(color) as usize].trailing_zeros() as usize];
+        let occ = self.full_occ();
+        let checkers = self.checkers(color);
+
+        let enemy_rooks = self.bitboard[Rook.as_color(enemy) as usize] | self.bitboard[Queen.as_color(enemy) as usize];
+        let enemy_bishops = self.bitboard[Bishop.as_color(enemy) as usize] | self.bitboard[Queen.as_color(enemy) as usize];
+        let mut pinned = 0;
+        let mut snipers = get_rook_moves(king as usize, 0) & enemy_rooks
+            | get_bishop_moves(king as usize, 0) & enemy_bishops;
+        while snipers != 0 {
+            let sniper = snipers.trailing_zeros() as usize;
+            let blockers = between(king as usize, sniper) & occ;
+            if blockers.count_ones() == 1 {
+                pinned |= blockers & self.occ(color);
+            }
+            snipers &= snipers - 1;
+        }
+
+        let through_king = occ ^ king.mask();
+        let mut attacked = 0;
+        for piece in Piece::pieces_of_color(enemy) {
+            let mut mask = self.bitboard[piece as usize];
+            while mask != 0 {
+                let square = mask.trailing_zeros() as usize;
+                attacked |= match piece.as_generic() {
+                    Rook => get_rook_moves(square, through_king),
+                    Knight => KNIGHT_MOVES[square],
+                    Bishop => get_bishop_moves(square, through_king),
+                    Queen => get_queen_moves(square, through_king),
+                    King => KING_MOVES[square],
+                    Pawn => PAWN_ATTACKS[enemy as usize][square],
+                };
+                mask &= mask - 1;
+            }
+        }
+
+        let check_mask = match checkers.count_ones() {
+            0 => ALL_SQUARES,
+            1 => checkers | between(king as usize, checkers.trailing_zeros() as usize),
+            _ => NO_SQUARES,
+        };
+        Legality { king, checkers, pinned, attacked, check_mask }
+    }
+
+    fn castling_targets(&self, legality: &Legality) -> u64 {
+        let mut mask = 0;
+        for q in Quadrant::ALL {
+            if legality.checkers == 0
+                && q.color() == self.turn()
+                && self.has_castling_rights(q)
+                && q.to_clear_mask() & self.full_occ() == 0
+                && q.no_attack_mask() & legality.attacked == 0 {
+                mask |= q.king_end().mask();
+            }
+        }
+        mask
+    }
+
+    fn is_legal_ep(&self, origin: Square, legality: &Legality) -> bool {
+        // Both pawns leave their squares at once, which can uncover the king
+        // along the rank, so the king's attackers are recomputed outright
+        let captured = match self.turn() {
+            Color::White => self.ep_square() as usize - 8,
+            Color::Black => self.ep_square() as usize + 8,
+        };
+        let occ = self.full_occ() ^ origin.mask() ^ SQUARE[captured] | self.ep_mask();
+        self.attackers_to(legality.king, occ) & self.enemy_occ() == 0
+    }
+
+    pub fn legal_targets(&self, piece: Piece, origin: Square, legality: &Legality) -> u64 {
+        // Legal destinations of the piece of the side to move on the origin
+        let pseudo = piece.moveset().pseudo_legal_threats(self, origin);
+        match piece.as_generic() {
+            King => pseudo & !self.self_occ() & !legality.attacked | self.castling_targets(legality),
+            generic => {
+                let pin = match legality.pinned & origin.mask() {
+                    0 => ALL_SQUARES,
+                    _ => line(legality.king as usize, origin as usize),
+                };
+                let targets = match generic {
+                    Pawn => pseudo & self.enemy_occ()
+                        | piece.moveset().pseudo_legal_nonthreats(self, origin),
+                    _ => pseudo & !self.self_occ(),
+                };
+                let ep = match generic == Pawn && pseudo & self.ep_mask() != 0 && self.is_legal_ep(origin, legality) {
+                    true => self.ep_mask(),
+                    false => NO_SQUARES,
+                };
+                targets & legality.check_mask & pin | ep
+            },
+        }
+    }
+
+    pub fn generate_legal_filtered<F>(&self, moves: &mut [Move; MAX_LEGAL_MOVES], filter: F) -> usize
+    where
+        F: Fn(&GameState, Piece) -> u64,
+    {
+        // Legal moves whose destinations pass the filter for the moving piece
+        let legality = self.legality();
+        let mut i = 0;
+        for piece in Piece::pieces_of_color(self.turn()) {
+            let mut piece_mask = self.bitboard[piece as usize];
+            while piece_mask != 0 {
+                let origin = Square::ALL[piece_mask.trailing_zeros() as usize];
+                let mut moves_mask = self.legal_targets(piece, origin, &legality) & filter(self, piece);
+                while moves_mask != 0 {
+                    let dest = Square::ALL[moves_mask.trailing_zeros() as usize];
+                    match (piece.as_generic(), dest.is_promotion_square()) {
+                        (Pawn, true) => for promo in Promotion::ALL {
+                            moves[i] = Move::new(self, origin, dest, Some(promo));
+                            i += 1;
+                        },
+                        _ => {
+                            moves[i] = Move::new(self, origin, dest, None);
+                            i += 1;
+                        },
+                    }
+                    moves_mask ^= dest.mask();
+                }
+                piece_mask ^= origin.mask();
+            }
+        }
+        i
+    }
+
+    pub fn generate_legal_moves(&mut self, moves: &mut [Move; MAX_LEGAL_MOVES]) -> usize {
+        // Returns number of legal moves
+        self.generate_legal_filtered(moves, |_, _| ALL_SQUARES)
+    }
+}
@@ -35,31 +35,26 @@ impl Moveset {
             BishopMove => get_bishop_moves(square as usize, state.bitboard[FULL_OCCUPANCY]),
             QueenMove => get_queen_moves(square as usize, state.bitboard[FULL_OCCUPANCY]),
             KingMove => KING_MOVES[square as usize],
-            WhitePawnMove => PAWN_ATTACKS[White as usize][square as usize] 
-                & (state.bitboard[BLACK_OCCUPANCY] | state.ep_mask()),
-            BlackPawnMove => PAWN_ATTACKS[Black as usize][square as usize] 
-                & (state.bitboard[WHITE_OCCUPANCY] | state.ep_mask()),
+            WhitePawnMove => PAWN_ATTACKS[White as usize][square as usize],
+            BlackPawnMove => PAWN_ATTACKS[Black as usize][square as usize],
         }
     }
 
-    pub fn pseudo_legal_nonthreats(self, state: &mut GameState, square: Square) -> u64 {
+    pub fn pseudo_legal_nonthreats(self, state: &GameState, square: Square) -> u64 {
         match self {
             KingMove => {
-                // The turn is temporarily flipped to check threats
-                state.set_turn(state.turn().inv());
                 let mut mask: u64 = 0;
                 for q in Quadrant::ALL {
                     let blockers = state.bitboard[FULL_OCCUPANCY];
                     let castling_cond = state.has_castling_rights(q)
-                        && state.turn() != q.color()  // Recall the turn has been flipped
+                        && state.turn() == q.color()
                         && q.to_clear_mask() & blockers == 0
-                        && q.no_attack_mask() & state.pseudo_legal_threats(state.turn()) == 0;
+                        && q.no_attack_mask() & state.pseudo_legal_threats(state.turn().inv()) == 0;
                     mask |= match castling_cond {
                         true => q.king_end().mask(),
                         false => 0,
                     };
                 }
-                state.set_turn(state.turn().inv());
                 mask
             },
             WhitePawnMove => {
@@ -78,11 +73,15 @@ impl Moveset {
         }
     }
 
-    pub fn pseudo_legal_moves(self, state: &mut GameState, square: Square) -> u64 {
+    pub fn pseudo_legal_moves(self, state: &GameState, square: Square) -> u64 {
         // Moves are all legal unless it puts self in check.
-        !state.self_occ() & (
-            self.pseudo_legal_threats(state, square) 
-            | self.pseudo_legal_nonthreats(state, square))
+        let threats = match self {
+            // Pawns only move diagonally when capturing
+            WhitePawnMove | BlackPawnMove => self.pseudo_legal_threats(state, square)
+                & (state.enemy_occ() | state.ep_mask()),
+            _ => self.pseudo_legal_threats(state, square),
+        };
+        !state.self_occ() & (threats | self.pseudo_legal_nonthreats(state, square))
     }
 }
 
@@ -130,6 +129,27 @@ impl GameState {
         i
     }
 
+    pub const fn attackers(&self, square: Square, color: Color) -> u64 {
+        // Pieces of the given color attacking the square
+        let occ = self.bitboard[FULL_OCCUPANCY];
+        let rooks = self.bitboard[Rook.as_color(color) as usize]
+            | self.bitboard[Queen.as_color(color) as usize];
+        let bishops = self.bitboard[Bishop.as_color(color) as usize]
+            | self.bitboard[Queen.as_color(color) as usize];
+        get_rook_moves(square as usize, occ) & rooks
+            | get_bishop_moves(square as usize, occ) & bishops
+            | KNIGHT_MOVES[square as usize] & self.bitboard[Knight.as_color(color) as usize]
+            | KING_MOVES[square as usize] & self.bitboard[King.as_color(color) as usize]
+            | PAWN_ATTACKS[color.inv() as usize][square as usize]
+                & self.bitboard[Pawn.as_color(color) as usize]
+    }
+
+    pub const fn checkers(&self, color: Color) -> u64 {
+        // Enemy pieces giving check to the king of the given color
+        let king = self.bitboard[King.as_color(color) as usize];
+        self.attackers(Square::ALL[king.trailing_zeros() as usize], color.inv())
+    }
+
     pub fn in_check(&self, color: Color) -> bool {
         0 != self.bitboard[King.as_color(color) as usize] & self.pseudo_legal_threats(color.inv())
     }
@@ -137,13 +157,63 @@ impl GameState {
     pub fn is_legal(&mut self, mv: Move) -> bool {
         // Only check for self-checks. Else guaranteed to be legal.
         self.push_partial(mv);
-        let result = self.in_check(self.turn());
+        let result = !self.in_check(self.turn());
         self.pop_partial(mv);
         result
     }
 
-    pub fn generate_legal_moves(&mut self, moves: &mut [Move; MAX_LEGAL_MOVES]) -> usize {
-        // Returns number of legal moves
+    pub fn try_move(
+        &self,
+        origin: Square,
+        destination: Square,
+        promotion: Option<Promotion>
+    ) -> Result<Move, IllegalMove> {
+        // Builds a move from user input, explaining why it is illegal if so
+        let piece = match self.piece_at(origin) {
+            None => return Err(IllegalMove::EmptySquareMove),
+            Some(p) => p,
+        };
+        if piece.color() != self.turn() {
+            return Err(IllegalMove::OpponentPieceMove);
+        }
+        if self.self_occ() & destination.mask() != 0 {
+            return Err(IllegalMove::AlliedCapture);
+        }
+        let promotes = piece.as_generic() == Pawn && destination.is_promotion_square();
+        if promotes != promotion.is_some() {
+            return Err(IllegalMove::InvalidPromotion);
+        }
+
+        let castling = Quadrant::ALL.into_iter().find(|q| q.king() == piece
+            && q.king_start() == origin
+            && q.king_end() == destination);
+        if let Some(q) = castling {
+            if !self.has_castling_rights(q) || q.to_clear_mask() & self.full_occ() != 0 {
+                return Err(IllegalMove::InvalidMove);
+            }
+            if self.in_check(self.turn()) {
+                return Err(IllegalMove::CastleOutOfCheck);
+            }
+            return match q.no_attack_mask() & self.pseudo_legal_threats(self.turn().inv()) {
+                0 => Ok(Move::new(self, origin, destination, None)),
+                _ => Err(IllegalMove::CastleThroughCheck),
+            };
+        }
+
+        if piece.moveset().pseudo_legal_moves(self, origin) & destination.mask() == 0 {
+            return Err(IllegalMove::InvalidMove);
+        }
+        let mv = Move::new(self, origin, destination, promotion);
+        let mut after = self.clone();
+        after.push_partial(mv);
+        match after.in_check(self.turn()) {
+            true => Err(IllegalMove::InCheck),
+            false => Ok(mv),
+        }
+    }
+
+    pub fn generate_legal_moves_by_make_unmake(&mut self, moves: &mut [Move; MAX_LEGAL_MOVES]) -> usize {
+        // Returns number of legal moves. The slower approach, kept as a reference.
         let mut n = self.generate_pseudo_legal_moves(moves);
         let mut i = 0;
         while i < n {
@@ -51,6 +51,7 @@ fen_info bits:
 9-16    (8)     Half-move counter
 17-31   (15)    Full-move counter
 */
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct GameState {
     // See constants above for last 3 indicies
     pub bitboard: [u64; Piece::ALL.len() + 3],
@@ -136,7 +137,7 @@ impl GameState {
 
 
     const fn set_fen_bits(&mut self, offset: u32, num_bits: u32, bits: u32) {
-        self.fen_info = self.fen_info & !((1 << num_bits) - 1 << offset) | bits << offset;
+        self.fen_info = self.fen_info & !(((1 << num_bits) - 1) << offset) | bits << offset;
     }
 
     
@@ -157,7 +158,7 @@ impl GameState {
         // TODO perhaps optimize
         self.zobrist_hash ^= zobrist::CASTLING[self.castling_code() as usize];
         // Optimization over set_castling
-        self.fen_info &= !(1 << CASTLING_OFFSET + castling as u32);
+        self.fen_info &= !(1 << (CASTLING_OFFSET + castling as u32));
         self.zobrist_hash ^= zobrist::CASTLING[self.castling_code() as usize];
     }
 
@@ -169,7 +170,7 @@ impl GameState {
     
     // TODO make private to fen
     pub const fn turn_code(&self) -> u32 {
-        self.fen_info >> TURN_OFFSET & 1
+        (self.fen_info >> TURN_OFFSET) & 1
     }
 
     pub const fn turn(&self) -> Color {
@@ -205,13 +206,13 @@ impl GameState {
             true => zobrist::EP_FILE[self.ep_file_num()],
             false => 0,
         };
-        self.fen_info &= !0b1111 << EP_LEGAL_OFFSET;
+        self.fen_info &= !(0b1111 << EP_LEGAL_OFFSET);
     }
 
 
     // TODO make private to fen
     pub const fn ep_code(&self) -> u32 {
-        self.fen_info >> EP_TARGET_OFFSET & 0b111
+        (self.fen_info >> EP_TARGET_OFFSET) & 0b111
     }
 
     pub const fn ep_file_num(&self) -> usize {
@@ -237,10 +238,7 @@ impl GameState {
     }
 
     pub const fn ep_square_num(&self) -> usize {
-        self.ep_file_num() + match self.turn() {
-            Color::White => Rank::Rank3.offset(),
-            Color::Black => Rank::Rank6.offset(),
-        }
+        self.ep_file_num() + self.ep_rank().offset()
     }
 
     pub const fn ep_square(&self) -> Square {
@@ -257,12 +255,12 @@ impl GameState {
     pub const fn set_ep_target(&mut self, file: File) {
         self.deny_ep();
         self.zobrist_hash ^= zobrist::EP_FILE[file as usize];
-        self.fen_info |= ((file as u32) << 1) + 1 << EP_LEGAL_OFFSET;
+        self.fen_info |= (((file as u32) << 1) + 1) << EP_LEGAL_OFFSET;
     }
     
 
     pub const fn halfmove_ctr(&self) -> u32 {
-        self.fen_info >> HALFMOVE_CTR_OFFSET & !((1 << HALFMOVE_CTR_BITS + 1) - 1)
+        (self.fen_info >> HALFMOVE_CTR_OFFSET) & ((1 << HALFMOVE_CTR_BITS) - 1)
     }
 
     pub const fn is_50_move_rule(&self) -> bool {
@@ -295,7 +293,7 @@ impl GameState {
     const fn change_piece_bitboard(&mut self, piece: Piece, square: Square) {
         // Remove or put is the same. Should not put piece on occupied square.
         // Need to update self.piece_on_square on function exit.
-        self.bitboard[piece as usize] |= square.mask();
+        self.bitboard[piece as usize] ^= square.mask();
         self.bitboard[piece.occ_index()] ^= square.mask();
         self.bitboard[FULL_OCCUPANCY] ^= square.mask();
         self.zobrist_hash ^= zobrist::PIECES[piece as usize][square as usize];
@@ -303,11 +301,11 @@ impl GameState {
 
     pub const fn remove_piece(&mut self, piece: Piece, square: Square) {
         self.change_piece_bitboard(piece, square);
-        self.piece_on_square[piece as usize] = None;
+        self.piece_on_square[square as usize] = None;
     }
 
     pub const fn put_piece(&mut self, piece: Piece, square: Square) {
         self.change_piece_bitboard(piece, square);
-        self.piece_on_square[piece as usize] = Some(piece);
+        self.piece_on_square[square as usize] = Some(piece);
     }
 }
@@ -0,0 +1,112 @@
+// Static exchange evaluation: the material outcome of the captures on one square
+// https://www.chessprogramming.org/Static_Exchange_Evaluation
+
+use {
+    crate::{
+        board::{
+            color::*,
+            piece::{*, GenericPiece::*},
+            square::*,
+        },
+        hashing::{
+            bitmask::*,
+            magic::*,
+        },
+    },
+    super::{
+        board_move::*,
+        position::*,
+    },
+};
+
+
+// Indexed by GenericPiece. The king outweighs any exchange it could end.
+pub const SEE_VALUES: [i32; 6] = [500, 300, 300, 900, 20_000, 100];
+
+// Cheapest attackers first
+const ATTACKER_ORDER: [GenericPiece; 6] = [Pawn, Knight, Bishop, Rook, Queen, King];
+
+// Longest possible exchange on one square, with room for the first move
+const MAX_EXCHANGE: usize = 34;
+
+
+impl GameState {
+
+    pub const fn attackers_to(&self, square: Square, occ: u64) -> u64 {
+        // Pieces of both colors attacking the square through the given occupancy
+        let rooks = self.bitboard[Piece::WhiteRook as usize] | self.bitboard[Piece::BlackRook as usize]
+            | self.bitboard[Piece::WhiteQueen as usize] | self.bitboard[Piece::BlackQueen as usize];
+        let bishops = self.bitboard[Piece::WhiteBishop as usize] | self.bitboard[Piece::BlackBishop as usize]
+            | self.bitboard[Piece::WhiteQueen as usize] | self.bitboard[Piece::BlackQueen as usize];
+        let knights = self.bitboard[Piece::WhiteKnight as usize] | self.bitboard[Piece::BlackKnight as usize];
+        let kings = self.bitboard[Piece::WhiteKing as usize] | self.bitboard[Piece::BlackKing as usize];
+        (get_rook_moves(square as usize, occ) & rooks
+            | get_bishop_moves(square as usize, occ) & bishops
+            | KNIGHT_MOVES[square as usize] & knights
+            | KING_MOVES[square as usize] & kings
+            | PAWN_ATTACKS[Color::Black as usize][square as usize] & self.bitboard[Piece::WhitePawn as usize]
+            | PAWN_ATTACKS[Color::White as usize][square as usize] & self.bitboard[Piece::BlackPawn as usize])
+            & occ
+    }
+
+    fn least_valuable_attacker(&self, attackers: u64, color: Color) -> Option<(GenericPiece, u64)> {
+        ATTACKER_ORDER.into_iter().find_map(|piece| {
+            match attackers & self.bitboard[piece.as_color(color) as usize] {
+                0 => None,
+                mask => Some((piece, mask & mask.wrapping_neg())),
+            }
+        })
+    }
+
+    pub fn see(&self, mv: Move) -> i32 {
+        // Material gained by the mover when both sides keep recapturing on the
+        // destination with their cheapest piece, each free to stop when behind.
+        // Pins are ignored; a king only recaptures onto an undefended square.
+        if mv.is_castling() {
+            return 0;
+        }
+        let to = mv.destination_square();
+        let mut gain = [0; MAX_EXCHANGE];
+        gain[0] = match mv.captured_piece() {
+            Some(piece) => SEE_VALUES[piece.as_generic() as usize],
+            None => 0,
+        };
+        if mv.is_promotion() {
+            gain[0] += SEE_VALUES[mv.destination_piece().as_generic() as usize] - SEE_VALUES[Pawn as usize];
+        }
+
+        let mut occ = self.full_occ() ^ mv.origin_square().mask();
+        if mv.is_ep_capture() {
+            occ ^= mv.captured_square().mask();
+        }
+        let mut attackers = self.attackers_to(to, occ);
+        // Value of the piece standing on the destination, liable to be captured next
+        let mut on_square = SEE_VALUES[mv.destination_piece().as_generic() as usize];
+        let mut color = mv.color().inv();
+        let mut d = 0;
+
+        while let Some((piece, from)) = self.least_valuable_attacker(attackers, color) {
+            if piece == King && attackers & self.occ(color.inv()) != 0 {
+                break;
+            }
+            d += 1;
+            gain[d] = on_square - gain[d - 1];
+            // Rescanning reveals sliders lined up behind the capturer
+            occ ^= from;
+            attackers = self.attackers_to(to, occ);
+            on_square = SEE_VALUES[piece as usize];
+            color = color.inv();
+        }
+
+        while d > 0 {
+            gain[d - 1] = -(-gain[d - 1]).max(gain[d]);
+            d -= 1;
+        }
+        gain[0]
+    }
+
+    pub fn see_ge(&self, mv: Move, threshold: i32) -> bool {
+        // Whether the exchange started by the move wins at least the threshold
+        self.see(mv) >= threshold
+    }
+}
@@ -0,0 +1,120 @@
+// Generators for one kind of legal move at a time, for staged move ordering
+// https://www.chessprogramming.org/Move_Generation#Staged_Move_Generation
+
+use {
+    crate::{
+        board::{
+            color::*,
+            direction::Direction::*,
+            piece::GenericPiece::*,
+            square::*,
+        },
+        hashing::{
+            bitmask::*,
+            magic::*,
+        },
+    },
+    super::{
+        board_move::*,
+        move_gen::*,
+        position::*,
+    },
+};
+
+
+impl GameState {
+
+    pub fn generate_captures(&mut self, moves: &mut [Move; MAX_LEGAL_MOVES]) -> usize {
+        // Legal captures, en passant included, and every promotion
+        self.generate_legal_filtered(moves, |state, piece| match piece.as_generic() {
+            Pawn => state.enemy_occ() | state.ep_mask() | PAWN_PROMOTION_SQUARES,
+            _ => state.enemy_occ(),
+        })
+    }
+
+    pub fn generate_quiets(&mut self, moves: &mut [Move; MAX_LEGAL_MOVES]) -> usize {
+        // Legal moves that neither capture nor promote, castling included
+        self.generate_legal_filtered(moves, |state, piece| match piece.as_generic() {
+            Pawn => !state.enemy_occ() & !state.ep_mask() & !PAWN_PROMOTION_SQUARES,
+            _ => !state.enemy_occ(),
+        })
+    }
+
+    pub fn generate_evasions(&mut self, moves: &mut [Move; MAX_LEGAL_MOVES]) -> usize {
+        // Legal moves out of check: the king steps away, or against a single
+        // checker another piece captures it or blocks the line it checks along
+        let color = self.turn();
+        let king = Square::ALL[self.bitboard[King.as_color(color) as usize].trailing_zeros() as usize];
+        let checkers = self.checkers(color);
+        let blocks = match checkers.count_ones() {
+            1 => checkers | between(king as usize, checkers.trailing_zeros() as usize),
+            _ => 0,
+        };
+        // A pawn that has just double pushed into check can be taken en passant
+        let pushed = match color {
+            Color::White => South.shift(self.ep_mask(), 1),
+            Color::Black => North.shift(self.ep_mask(), 1),
+        };
+        let ep = match pushed & checkers {
+            0 => 0,
+            _ => self.ep_mask(),
+        };
+        self.generate_legal_filtered(moves, |_, piece| match piece.as_generic() {
+            King => KING_MOVES[king as usize],  // No castling out of check
+            Pawn => blocks | ep,
+            _ => blocks,
+        })
+    }
+
+    pub fn gives_check(&self, mv: Move) -> bool {
+        // Whether the move checks the enemy king, directly or by uncovering a slider
+        let color = mv.color();
+        let king = self.bitboard[King.as_color(color.inv()) as usize].trailing_zeros() as usize;
+        let mut occ = self.full_occ() ^ mv.origin_square().mask() | mv.destination_square().mask();
+        if mv.is_ep_capture() {
+            occ ^= mv.captured_square().mask();
+        }
+        // The castling rook is the piece that may give check
+        let (piece, square) = match mv.is_castling() {
+            true => {
+                let q = mv.get_castling();
+                occ ^= q.rook_start().mask() | q.rook_end().mask();
+                (Rook, q.rook_end())
+            },
+            false => (mv.destination_piece().as_generic(), mv.destination_square()),
+        };
+        let direct = match piece {
+            Rook => get_rook_moves(square as usize, occ),
+            Knight => KNIGHT_MOVES[square as usize],
+            Bishop => get_bishop_moves(square as usize, occ),
+            Queen => get_queen_moves(square as usize, occ),
+            King => 0,
+            Pawn => PAWN_ATTACKS[color as usize][square as usize],
+        };
+        // Sliders that stayed put, seen from the king through the new occupancy
+        let moved = mv.origin_square().mask() | match mv.is_castling() {
+            true => mv.get_castling().rook_start().mask(),
+            false => 0,
+        };
+        let rooks = (self.bitboard[Rook.as_color(color) as usize]
+            | self.bitboard[Queen.as_color(color) as usize]) & !moved;
+        let bishops = (self.bitboard[Bishop.as_color(color) as usize]
+            | self.bitboard[Queen.as_color(color) as usize]) & !moved;
+        direct & SQUARE[king] != 0
+            || get_rook_moves(king, occ) & rooks != 0
+            || get_bishop_moves(king, occ) & bishops != 0
+    }
+
+    pub fn generate_quiet_checks(&mut self, moves: &mut [Move; MAX_LEGAL_MOVES]) -> usize {
+        // Legal quiet moves that give check
+        let n = self.generate_quiets(moves);
+        let mut i = 0;
+        for j in 0..n {
+            if self.gives_check(moves[j]) {
+                moves[i] = moves[j];
+                i += 1;
+            }
+        }
+        i
+    }
+}
@@ -7,11 +7,11 @@ use crate::board::{
 pub fn print(mask: u64) {
     for i in 0..8 {
         let j = 7 - i;
-        let b = (mask & RANK[j]) >> 8 * j;
-        for k in 0..8 {
-            print!("{} ", match b & FILE[k] != 0 {true => "X", false => "."});
+        let b = (mask & RANK[j]) >> (8 * j);
+        for file in FILE {
+            print!("{} ", match b & file != 0 {true => "X", false => "."});
         }
-        println!("");
+        println!();
     }
 }
 
@@ -271,24 +271,54 @@ pub const RAY_CAST: [[u64; 64]; 8] = {
 };
 
 
+pub const fn between(a: usize, b: usize) -> u64 {
+    // Squares strictly between two squares on a shared line, else none
+    let mut i = 0;
+    while i < RAY_CAST.len() {
+        if a != b && RAY_CAST[i][a] & SQUARE[b] != 0 {
+            return RAY_CAST[i][a] & !RAY_CAST[i][b] & !SQUARE[a];
+        }
+        i += 1;
+    }
+    0
+}
+
+
+pub const fn line(a: usize, b: usize) -> u64 {
+    // The whole line through two distinct squares, edge to edge, else none
+    let mut i = 0;
+    while i < RAY_CAST.len() {
+        if a != b && RAY_CAST[i][a] & SQUARE[b] != 0 {
+            return RAY_CAST[i][a] | RAY_CAST[(i + 4) % 8][a];
+        }
+        i += 1;
+    }
+    0
+}
+
+
+const fn relevant_ray(direction: Direction, i: usize) -> u64 {
+    // A blocker on the last square of a ray never shortens it
+    let ray = direction.ray(i);
+    ray & direction.inv().shift(ray, 1) & !SQUARE[i]
+}
+
 const fn gen_piece_rays(relevant_occupancy_only: bool, is_rook: bool) -> [u64; 64] {
     let mut arr: [u64; 64] = [0; 64];
+    let directions = match is_rook {
+        true => [North, East, South, West],
+        false => [Northwest, Northeast, Southeast, Southwest],
+    };
     let mut i = 0;
     while i < arr.len() {
-        let rays = match is_rook {
-            true => North.ray(i)
-                | East.ray(i)
-                | South.ray(i)
-                | West.ray(i),
-            false => Northwest.ray(i)
-                | Northeast.ray(i)
-                | Southeast.ray(i)
-                | Southwest.ray(i)
-        };
-        arr[i] = match relevant_occupancy_only {
-            true => rays & !EDGES & !SQUARE[i],
-            false => rays,
-        };
+        let mut j = 0;
+        while j < directions.len() {
+            arr[i] |= match relevant_occupancy_only {
+                true => relevant_ray(directions[j], i),
+                false => directions[j].ray(i),
+            };
+            j += 1;
+        }
         i += 1;
     }
     arr
@@ -13,14 +13,14 @@ use super::bitmask::*;
 pub const fn get_rook_moves(square: usize, blockers: u64) -> u64 {
     let i = ROOK_MAGICS[square]
         .wrapping_mul(blockers & ROOK_RELEVANT_OCCUPANCY[square])
-        .wrapping_shr((64 - ROOK_BITS[square]) as u32);
+        .wrapping_shr(64 - ROOK_BITS[square]);
     ROOK_TABLE[square][i as usize]
 }
 
 pub const fn get_bishop_moves(square: usize, blockers: u64) -> u64 {
     let i = BISHOP_MAGICS[square]
         .wrapping_mul(blockers & BISHOP_RELEVANT_OCCUPANCY[square])
-        .wrapping_shr((64 - ROOK_BITS[square]) as u32);
+        .wrapping_shr(64 - BISHOP_BITS[square]);
     BISHOP_TABLE[square][i as usize]
 }
 
@@ -202,68 +202,64 @@ const fn sliding_moves(is_rook: bool, square: usize, blockers: u64) -> u64 {
         true => 0,
         false => 1
     };
-    moves |= sliding_move(blockers, r, c, 1 * m, 1);
-    moves |= sliding_move(blockers, r, c, 1, -1 * m);
-    moves |= sliding_move(blockers, r, c, -1 * m, -1);
-    moves |= sliding_move(blockers, r, c, -1, 1 * m);
+    moves |= sliding_move(blockers, r, c, m, 1);
+    moves |= sliding_move(blockers, r, c, 1, -m);
+    moves |= sliding_move(blockers, r, c, -m, -1);
+    moves |= sliding_move(blockers, r, c, -1, m);
     moves
 }
 
 
-const fn gen_magic_rook(
-    &(mut arr): &[u64; 1 << 12],
-    square: usize,
-    blockers: u64, 
-    mut i: usize
-) -> [u64; 1 << 12] {
-    assert!(square < 64);
-    let j = ROOK_MAGICS[square]
-        .wrapping_mul(blockers & ROOK_RELEVANT_OCCUPANCY[square])
-        .wrapping_shr((64 - ROOK_BITS[square]) as u32);
-    arr[j as usize] = sliding_moves(true, i, blockers);
-    while i < 64 {
-        if blockers & SQUARE[i] != 0 {
-            gen_magic_rook(&mut arr, square, blockers, i + 1);
+const fn gen_magic_rook(square: usize) -> [u64; 1 << 12] {
+    let mut arr: [u64; 1 << 12] = [0; 1 << 12];
+    let relevant = ROOK_RELEVANT_OCCUPANCY[square];
+    let mut blockers: u64 = 0;
+    loop {
+        let j = ROOK_MAGICS[square]
+            .wrapping_mul(blockers)
+            .wrapping_shr(64 - ROOK_BITS[square]);
+        arr[j as usize] = sliding_moves(true, square, blockers);
+        // Carry-rippler: steps through every subset of the relevant occupancy
+        blockers = blockers.wrapping_sub(relevant) & relevant;
+        if blockers == 0 {
+            break;
         }
-        i += 1
     }
     arr
 }
 
-const fn gen_magic_bishop(
-    &(mut arr): &[u64; 1 << 9],
-    square: usize,
-    blockers: u64,
-    mut i: usize
-) -> [u64; 1 << 9] {
-    let j = BISHOP_MAGICS[square]
-        .wrapping_mul(blockers & BISHOP_RELEVANT_OCCUPANCY[square])
-        .wrapping_shr((64 - BISHOP_BITS[square]) as u32);
-    arr[j as usize] = sliding_moves(false, i, blockers);
-    while i < 64 {
-        if blockers & SQUARE[square] != 0 {
-            gen_magic_bishop(&mut arr, square, blockers, i + 1);
+const fn gen_magic_bishop(square: usize) -> [u64; 1 << 9] {
+    let mut arr: [u64; 1 << 9] = [0; 1 << 9];
+    let relevant = BISHOP_RELEVANT_OCCUPANCY[square];
+    let mut blockers: u64 = 0;
+    loop {
+        let j = BISHOP_MAGICS[square]
+            .wrapping_mul(blockers)
+            .wrapping_shr(64 - BISHOP_BITS[square]);
+        arr[j as usize] = sliding_moves(false, square, blockers);
+        blockers = blockers.wrapping_sub(relevant) & relevant;
+        if blockers == 0 {
+            break;
         }
-        i += 1;
     }
     arr
 }
 
-pub const ROOK_TABLE: [[u64; 1 << 12]; 64] = {
+pub static ROOK_TABLE: [[u64; 1 << 12]; 64] = {
     let mut arr: [[u64; 1 << 12]; 64] = [[0; 1 << 12]; 64];
     let mut i = 0;
     while i < arr.len() {
-        arr[i] = gen_magic_rook(&arr[i], i, ROOK_RELEVANT_OCCUPANCY[i], 0);
+        arr[i] = gen_magic_rook(i);
         i += 1;
     }
     arr
 };
 
-pub const BISHOP_TABLE: [[u64; 1 << 9]; 64] = {
+pub static BISHOP_TABLE: [[u64; 1 << 9]; 64] = {
     let mut arr: [[u64; 1 << 9]; 64] = [[0; 1 << 9]; 64];
     let mut i = 0;
     while i < arr.len() {
-        arr[i] = gen_magic_bishop(&arr[i], i, BISHOP_RELEVANT_OCCUPANCY[i], 0);
+        arr[i] = gen_magic_bishop(i);
         i += 1
     }
     arr
@@ -3,3 +3,4 @@ pub mod game;
 pub mod hashing;
 pub mod parse;
 pub mod perft;
+pub mod search;
@@ -1,3 +1,5 @@
 pub mod chr;
 pub mod fen;
 pub mod pacn;
+pub mod pgn;
+pub mod san;
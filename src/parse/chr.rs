@@ -50,7 +50,7 @@ impl File {
 
     pub const fn from_chr(chr: char) -> Result<File, ConversionError> {
         match 'a' <= chr && chr <= 'h' {
-            true => Ok(File::ALL[(chr as u8 - 'a' as u8) as usize]),
+            true => Ok(File::ALL[(chr as u8 - b'a') as usize]),
             false => Err(InvalidFile(chr)),
         }
     }
@@ -68,7 +68,7 @@ impl Rank {
 
     pub const fn from_chr(chr: char) -> Result<Rank, ConversionError> {
         match '1' <= chr && chr <= '8' {
-            true => Ok(Rank::ALL[(chr as u8 - '1' as u8) as usize]),
+            true => Ok(Rank::ALL[(chr as u8 - b'1') as usize]),
             false => Err(InvalidRank(chr)),
         }
     }
@@ -126,14 +126,14 @@ impl Promotion {
     // No differentiation between white and black promotion in UCI protocol.
 
     pub const fn chr(self) -> char {
-        const CHARS: [char; 4] = ['r', 'k', 'b', 'q'];
+        const CHARS: [char; 4] = ['r', 'n', 'b', 'q'];
         CHARS[self as usize]
     }
 
     pub const fn from_chr(chr: char) -> Result<Promotion, ConversionError> {
         match chr {
             'r' => Ok(Promotion::Rook),
-            'k' => Ok(Promotion::Knight),
+            'n' => Ok(Promotion::Knight),
             'b' => Ok(Promotion::Bishop),
             'q' => Ok(Promotion::Queen),
             _ => Err(InvalidPromotion(chr))
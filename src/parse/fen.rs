@@ -1,5 +1,4 @@
 use {
-    std::cmp::max,
     crate::{
         board::{
             color::{*, Color::*},
@@ -88,7 +87,7 @@ impl GameState {
                 chars.push(i.chr());
             }
         }
-        if chars.len() == 0 {
+        if chars.is_empty() {
             chars.push('-');
         }
         String::from_iter(chars)
@@ -176,7 +175,7 @@ impl GameState {
                     },
                     _ => {
                         let piece = converts(Piece::from_chr(chr))? as usize;
-                        state.bitboard[piece] |= 1u64 << 8 * row + col as i32;
+                        state.bitboard[piece] |= 1u64 << (8 * row + col as i32);
                         col += 1;
                     },
                 }
@@ -240,10 +239,18 @@ impl GameState {
             (Some('-'), None) => return Err(FenError::MissingSection(FenSection::HalfmoveCounter)),
             (_, None) => return Err(FenError::ConversionError(ConversionError::IncompleteSquare)),
             (Some('-'), Some(' ')) => (),
-            (Some(f), Some(r)) => match state.ep_rank() == converts(Rank::from_chr(r))? {
-                true => state.set_ep_target(converts(File::from_chr(f))?),
-                false => return Err(FenError::InvalidEnPassant),
-            }
+            (Some(f), Some(r)) => {
+                match state.ep_rank() == converts(Rank::from_chr(r))? {
+                    true => state.set_ep_target(converts(File::from_chr(f))?),
+                    false => return Err(FenError::InvalidEnPassant),
+                }
+                i += 1;
+                match chars.next() {
+                    None => return Err(FenError::MissingSection(FenSection::HalfmoveCounter)),
+                    Some(' ') => (),
+                    _ => return Err(FenError::ExpectedSpace(i)),
+                }
+            },
         }
 
         // Halfmove counter
@@ -262,7 +269,7 @@ impl GameState {
 
         // Fullmove counter
         let s = String::from_iter(chars);
-        if s.len() == 0 {
+        if s.is_empty() {
             return Err(FenError::MissingSection(FenSection::FullmoveCounter));
         }
         match s.parse::<u32>() {
@@ -276,6 +283,9 @@ impl GameState {
             state.bitboard[BLACK_OCCUPANCY] |= state.bitboard[i + 6];
         }
         state.bitboard[FULL_OCCUPANCY] = state.occ(White) + state.occ(Black);
+        for s in Square::ALL {
+            state.piece_on_square[s as usize] = state.piece_at_bitboard(s);
+        }
 
         state.zobrist_hash = state.compute_zobrist();
 
@@ -286,24 +296,24 @@ impl GameState {
         for i in 0..12 {
             println!("{:?}", Piece::ALL[i]);
             bitmask::print(self.bitboard[i]);
-            println!("");
+            println!();
         }
 
         println!("White occupancy");
         bitmask::print(self.occ(White));
-        println!("");
+        println!();
 
         println!("Black occupancy");
         bitmask::print(self.occ(Black));
-        println!("");
+        println!();
 
         println!("Full occupancy");
         bitmask::print(self.full_occ());
-        println!("");
+        println!();
 
         println!("Piece on square");
         self.print_pretty();
-        println!("");
+        println!();
 
         println!(
             "To move: {} {:01b}", 
@@ -346,7 +356,7 @@ impl GameState {
                 };
                 print!("{} ", s);
             }
-            println!("");
+            println!();
         }
         println!("{}", self.fen());
     }
@@ -369,7 +379,7 @@ impl GameState {
         occ_mismatch(occ[White as usize] & occ[Black as usize])?;
         occ_mismatch(occ[White as usize] ^ self.occ(White))?;
         occ_mismatch(occ[Black as usize] ^ self.occ(Black))?;
-        occ_mismatch(self.occ(White) & self.occ(Black) ^ self.full_occ())?;
+        occ_mismatch((self.occ(White) | self.occ(Black)) ^ self.full_occ())?;
 
         for s in  Square::ALL {
             if self.piece_at(s) != self.piece_at_bitboard(s) {
@@ -379,16 +389,17 @@ impl GameState {
             }
         }
 
-        match self.ep_code() {
-            0 => (),
-            0b0010..0b1110 | 0b10000.. => return Err(
+        match self.ep_legal() {
+            false if self.ep_code() != 0 => return Err(
                 IllegalPosition::CorruptedBitboard(
                     CorruptedBitboard::InvalidEnPassantCode(self.ep_code()))),
-            _ => {
+            false => (),
+            true => {
                 let s = self.ep_square();
                 let enemy_expected = self.turn().inv().pawn_direction().shift(s.mask(), 1);
                 let enemy_actual = self.bitboard[Pawn.as_color(self.turn().inv()) as usize];
-                let allied_expected = bitmask::PAWN_ATTACKS[self.turn() as usize][s as usize];
+                // Allied pawns attacking s stand where an enemy pawn on s would attack
+                let allied_expected = bitmask::PAWN_ATTACKS[self.turn().inv() as usize][s as usize];
                 let allied_actual = self.bitboard[Pawn.as_color(self.turn()) as usize];
                 if s.mask() & self.full_occ() != 0 {
                     return Err(IllegalPosition::EnPassantSquareOccupied);
@@ -414,7 +425,7 @@ impl GameState {
             let mut promotions_left = 8 - pawns;
             for (piece, starting_count) in STARTING_COUNTS {
                 let count = self.count_piece_validate(piece.as_color(color), promotions_left + starting_count)?;
-                promotions_left -= max(0, count - starting_count);
+                promotions_left -= count.saturating_sub(starting_count);
             }
             // TODO check for same color bishops with no pawn promotions
             let p = Bishop.as_color(color);
@@ -41,6 +41,15 @@ fn expect<T>(option: Option<T>) -> Result<T, PacnError> {
 
 impl Move {
 
+    pub fn pacn(self) -> String {
+        let mut s = String::from_iter(self.origin_square().chrs());
+        s.extend(self.destination_square().chrs());
+        if let Some(p) = self.promotion() {
+            s.push(p.chr());
+        }
+        s
+    }
+
     pub fn from_str(game: &GameState, s: &str) -> Result<Move, PacnError> {
         let mut chars = s.chars();
         let origin = Square::from_file_rank(
@@ -57,6 +66,9 @@ impl Move {
             }
         };
 
-        Ok(Move::new(game, origin, destination, promotion))
+        match game.try_move(origin, destination, promotion) {
+            Err(e) => Err(PacnError::IllegalMove(e)),
+            Ok(mv) => Ok(mv),
+        }
     }
 }
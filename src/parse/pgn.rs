@@ -0,0 +1,507 @@
+// Portable game notation
+
+use {
+    std::{
+        io::{self, BufRead, Lines},
+        iter::Peekable,
+        str::Chars,
+    },
+    crate::{
+        board::color::Color::*,
+        game::{
+            board_move::*,
+            position::*,
+        },
+    },
+    super::{
+        fen::FenError,
+        san::*,
+    },
+};
+
+
+#[derive(Debug)]
+pub enum PgnError {
+    Io(io::Error),
+    MalformedTag(String),
+    InvalidFen(FenError),
+    IllegalMove(String, SanError),  // The offending SAN token
+    UnexpectedToken(String),
+    UnterminatedComment,
+    UnterminatedVariation,
+}
+
+#[derive(Debug)]
+pub struct PgnGameError {
+    pub game: usize,  // 1-based
+    pub ply: usize,  // Plies from the starting position, within the variation if nested
+    pub error: PgnError,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PgnLine {
+    pub comment: Option<String>,  // Before the first move
+    pub moves: Vec<PgnNode>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PgnNode {
+    pub mv: Move,
+    pub nags: Vec<u8>,
+    pub comment: Option<String>,
+    pub variations: Vec<PgnLine>,  // Alternatives to mv, played from the same position
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PgnGame {
+    pub tags: Vec<(String, String)>,
+    pub start: GameState,
+    pub mainline: PgnLine,
+    pub result: String,
+}
+
+
+pub const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+
+// Tags that export format always writes first, with their placeholder values
+pub const SEVEN_TAG_ROSTER: [(&str, &str); 7] = [
+    ("Event", "?"), ("Site", "?"), ("Date", "????.??.??"), ("Round", "?"),
+    ("White", "?"), ("Black", "?"), ("Result", "*"),
+];
+
+pub const MAX_LINE_WIDTH: usize = 80;
+
+// Move suffix annotations and the NAGs they stand for
+pub const SUFFIX_NAGS: [(&str, u8); 6] = [
+    ("!", 1), ("?", 2), ("!!", 3), ("??", 4), ("!?", 5), ("?!", 6),
+];
+
+
+impl PgnNode {
+
+    pub fn new(mv: Move) -> PgnNode {
+        PgnNode { mv, nags: Vec::new(), comment: None, variations: Vec::new() }
+    }
+}
+
+impl PgnGame {
+
+    pub fn new(start: GameState, moves: &[Move]) -> PgnGame {
+        PgnGame {
+            tags: Vec::new(),
+            start,
+            mainline: PgnLine {
+                comment: None,
+                moves: moves.iter().map(|&mv| PgnNode::new(mv)).collect(),
+            },
+            result: String::from("*"),
+        }
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+    }
+
+    pub fn moves(&self) -> Vec<Move> {
+        self.mainline.moves.iter().map(|node| node.mv).collect()
+    }
+
+    pub fn end(&self) -> GameState {
+        let mut state = self.start.clone();
+        for mv in self.moves() {
+            state.push(mv);
+        }
+        state
+    }
+}
+
+
+enum Token {
+    Symbol(String),
+    Comment(String),
+    Nag(u8),
+    Open,
+    Close,
+    Result(String),
+}
+
+struct Tokens<'a> {
+    chars: Peekable<Chars<'a>>,
+    nag: Option<u8>,  // Suffix annotation split off the previous symbol
+}
+
+impl Iterator for Tokens<'_> {
+    type Item = Result<Token, PgnError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(nag) = self.nag.take() {
+            return Some(Ok(Token::Nag(nag)));
+        }
+        loop {
+            let c = self.chars.next()?;
+            match c {
+                '{' => {
+                    let mut comment = String::new();
+                    loop {
+                        match self.chars.next() {
+                            None => return Some(Err(PgnError::UnterminatedComment)),
+                            Some('}') => break,
+                            Some(c) => comment.push(c),
+                        }
+                    }
+                    return Some(Ok(Token::Comment(comment.split_whitespace().collect::<Vec<_>>().join(" "))));
+                },
+                ';' => {
+                    let comment: String = self.chars.by_ref().take_while(|&c| c != '\n').collect();
+                    return Some(Ok(Token::Comment(String::from(comment.trim()))));
+                },
+                '(' => return Some(Ok(Token::Open)),
+                ')' => return Some(Ok(Token::Close)),
+                '$' => {
+                    let mut digits = String::new();
+                    while let Some(d) = self.chars.next_if(char::is_ascii_digit) {
+                        digits.push(d);
+                    }
+                    return Some(match digits.parse::<u8>() {
+                        Ok(nag) => Ok(Token::Nag(nag)),
+                        Err(_) => Err(PgnError::UnexpectedToken(format!("${}", digits))),
+                    });
+                },
+                _ if c.is_whitespace() => continue,
+                _ => {
+                    let mut symbol = String::from(c);
+                    while let Some(c) = self.chars.next_if(|&c| !c.is_whitespace() && !"{}();$".contains(c)) {
+                        symbol.push(c);
+                    }
+                    if RESULTS.contains(&symbol.as_str()) {
+                        return Some(Ok(Token::Result(symbol)));
+                    }
+                    // Move numbers, possibly run into the move as in 1.e4
+                    let san = symbol.trim_start_matches(|c: char| c.is_ascii_digit());
+                    let san = match san.starts_with('.') {
+                        true => san.trim_start_matches('.'),
+                        false => symbol.as_str(),
+                    };
+                    if san.is_empty() {
+                        continue;
+                    }
+                    let suffix = &san[san.trim_end_matches(['!', '?']).len()..];
+                    self.nag = SUFFIX_NAGS.iter().find(|(s, _)| *s == suffix).map(|&(_, nag)| nag);
+                    let san = match self.nag {
+                        Some(_) => &san[..san.len() - suffix.len()],
+                        None => san,
+                    };
+                    return Some(Ok(Token::Symbol(String::from(san))));
+                },
+            }
+        }
+    }
+}
+
+
+fn append_comment(comment: &mut Option<String>, text: String) {
+    *comment = match comment.take() {
+        None => Some(text),
+        Some(c) => Some(format!("{} {}", c, text)),
+    };
+}
+
+struct MovetextParser<'a> {
+    tokens: Tokens<'a>,
+    ply: usize,
+    result: Option<String>,
+}
+
+impl MovetextParser<'_> {
+
+    fn parse_line(&mut self, state: &mut GameState, nested: bool) -> Result<PgnLine, PgnError> {
+        let mut line = PgnLine::default();
+        let mut before: Option<GameState> = None;  // Position before the last move
+        loop {
+            let token = match self.tokens.next() {
+                None => match nested {
+                    true => return Err(PgnError::UnterminatedVariation),
+                    false => break,
+                },
+                Some(token) => token?,
+            };
+            match token {
+                Token::Comment(text) => match line.moves.last_mut() {
+                    None => append_comment(&mut line.comment, text),
+                    Some(node) => append_comment(&mut node.comment, text),
+                },
+                Token::Nag(nag) => match line.moves.last_mut() {
+                    None => return Err(PgnError::UnexpectedToken(format!("${}", nag))),
+                    Some(node) => node.nags.push(nag),
+                },
+                Token::Open => {
+                    let (Some(node), Some(before)) = (line.moves.last_mut(), &before) else {
+                        return Err(PgnError::UnexpectedToken(String::from("(")));
+                    };
+                    let ply = self.ply;
+                    self.ply -= 1;
+                    node.variations.push(self.parse_line(&mut before.clone(), true)?);
+                    self.ply = ply;
+                },
+                Token::Close => match nested {
+                    true => break,
+                    false => return Err(PgnError::UnexpectedToken(String::from(")"))),
+                },
+                Token::Result(result) => match nested {
+                    true => return Err(PgnError::UnexpectedToken(result)),
+                    false => {
+                        self.result = Some(result);
+                        break;
+                    },
+                },
+                Token::Symbol(san) => {
+                    let mv = match Move::from_san(state, &san) {
+                        Err(e) => return Err(PgnError::IllegalMove(san, e)),
+                        Ok(mv) => mv,
+                    };
+                    before = Some(state.clone());
+                    state.push(mv);
+                    self.ply += 1;
+                    line.moves.push(PgnNode::new(mv));
+                },
+            }
+        }
+        Ok(line)
+    }
+}
+
+
+fn parse_tag(line: &str) -> Result<(String, String), PgnError> {
+    let malformed = || PgnError::MalformedTag(String::from(line));
+    let inner = line.strip_prefix('[')
+        .and_then(|s| s.strip_suffix(']'))
+        .ok_or_else(malformed)?
+        .trim();
+    let (name, value) = inner.split_once(char::is_whitespace).ok_or_else(malformed)?;
+    let value = value.trim()
+        .strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .ok_or_else(malformed)?;
+    let mut unescaped = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.push(chars.next().ok_or_else(malformed)?),
+            _ => unescaped.push(c),
+        }
+    }
+    Ok((String::from(name), unescaped))
+}
+
+pub fn parse_game(tag_lines: &[String], movetext: &str) -> Result<PgnGame, (usize, PgnError)> {
+    // Errors come with the ply at which they occurred
+    let mut tags = Vec::new();
+    for line in tag_lines {
+        tags.push(parse_tag(line).map_err(|e| (0, e))?);
+    }
+    let fen = tags.iter().find(|(name, _)| name == "FEN").map(|(_, v)| v.as_str());
+    let start = match GameState::from_fen(fen.unwrap_or(START_FEN)) {
+        Err(e) => return Err((0, PgnError::InvalidFen(e))),
+        Ok(state) => state,
+    };
+
+    let mut parser = MovetextParser {
+        tokens: Tokens { chars: movetext.chars().peekable(), nag: None },
+        ply: 0,
+        result: None,
+    };
+    let mainline = parser.parse_line(&mut start.clone(), false).map_err(|e| (parser.ply, e))?;
+    let result = parser.result
+        .or_else(|| tags.iter().find(|(name, _)| name == "Result").map(|(_, v)| v.clone()))
+        .unwrap_or(String::from("*"));
+    Ok(PgnGame { tags, start, mainline, result })
+}
+
+
+fn escape_tag(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn push_comment(tokens: &mut Vec<String>, comment: &str) {
+    // Split into words so that long comments wrap like the moves around them
+    let words: Vec<&str> = comment.split_whitespace().collect();
+    match words.len() {
+        0 => tokens.push(String::from("{}")),
+        n => for (i, word) in words.into_iter().enumerate() {
+            let open = match i { 0 => "{", _ => "" };
+            let close = match i == n - 1 { true => "}", false => "" };
+            tokens.push(format!("{}{}{}", open, word, close));
+        },
+    }
+}
+
+fn push_line(tokens: &mut Vec<String>, line: &PgnLine, state: &mut GameState) {
+    // Black moves need a number at the start of a line and after comments or variations
+    let mut numbered = false;
+    if let Some(comment) = &line.comment {
+        push_comment(tokens, comment);
+    }
+    for node in &line.moves {
+        match (state.turn(), numbered) {
+            (White, _) => tokens.push(format!("{}.", state.fullmove_ctr())),
+            (Black, false) => tokens.push(format!("{}...", state.fullmove_ctr())),
+            (Black, true) => (),
+        }
+        tokens.push(node.mv.san(state));
+        tokens.extend(node.nags.iter().map(|nag| format!("${}", nag)));
+        numbered = true;
+        if let Some(comment) = &node.comment {
+            push_comment(tokens, comment);
+            numbered = false;
+        }
+        for variation in node.variations.iter().filter(|v| !v.moves.is_empty()) {
+            let start = tokens.len();
+            push_line(tokens, variation, &mut state.clone());
+            tokens[start].insert(0, '(');
+            tokens.last_mut().unwrap().push(')');
+            numbered = false;
+        }
+        state.push(node.mv);
+    }
+}
+
+fn wrap(tokens: &[String]) -> String {
+    let mut text = String::new();
+    let mut width = 0;
+    for token in tokens {
+        if width > 0 && width + 1 + token.len() > MAX_LINE_WIDTH {
+            text.push('\n');
+            width = 0;
+        } else if width > 0 {
+            text.push(' ');
+            width += 1;
+        }
+        text.push_str(token);
+        width += token.len();
+    }
+    text
+}
+
+impl PgnGame {
+
+    pub fn to_pgn(&self) -> String {
+        // Export format: roster tags, remaining tags, a blank line, then wrapped movetext
+        let mut tags: Vec<(&str, String)> = Vec::new();
+        for (name, default) in SEVEN_TAG_ROSTER {
+            let value = match name {
+                "Result" => self.result.as_str(),
+                _ => self.tag(name).unwrap_or(default),
+            };
+            tags.push((name, String::from(value)));
+        }
+        let start_fen = self.start.fen();
+        let set_up = start_fen != START_FEN;
+        for (name, value) in &self.tags {
+            let skipped = SEVEN_TAG_ROSTER.iter().any(|(n, _)| n == name)
+                || name == "SetUp"
+                || name == "FEN";
+            if !skipped {
+                tags.push((name, value.clone()));
+            }
+        }
+        if set_up {
+            tags.push(("SetUp", String::from("1")));
+            tags.push(("FEN", start_fen));
+        }
+
+        let mut pgn = String::new();
+        for (name, value) in tags {
+            pgn.push_str(&format!("[{} \"{}\"]\n", name, escape_tag(&value)));
+        }
+        pgn.push('\n');
+        let mut tokens = Vec::new();
+        push_line(&mut tokens, &self.mainline, &mut self.start.clone());
+        tokens.push(self.result.clone());
+        pgn.push_str(&wrap(&tokens));
+        pgn.push_str("\n\n");
+        pgn
+    }
+}
+
+
+pub struct PgnReader<R: BufRead> {
+    lines: Lines<R>,
+    pending: Option<String>,  // First tag line of the next game
+    game: usize,
+}
+
+impl<R: BufRead> PgnReader<R> {
+
+    pub fn new(reader: R) -> PgnReader<R> {
+        PgnReader { lines: reader.lines(), pending: None, game: 0 }
+    }
+
+    fn read_game_text(&mut self) -> io::Result<Option<(Vec<String>, String)>> {
+        let mut tags = Vec::new();
+        let mut movetext = String::new();
+        let mut in_comment = false;
+        loop {
+            let line = match self.pending.take() {
+                Some(line) => line,
+                None => match self.lines.next() {
+                    None => break,
+                    Some(line) => line?,
+                },
+            };
+            let trimmed = line.trim();
+            if !in_comment {
+                let started = !movetext.trim().is_empty();
+                if trimmed.starts_with('%') {
+                    continue;
+                }
+                if trimmed.is_empty() {
+                    match started {
+                        true => break,
+                        false => continue,
+                    }
+                }
+                if trimmed.starts_with('[') {
+                    if started {
+                        self.pending = Some(line);
+                        break;
+                    }
+                    tags.push(String::from(trimmed));
+                    continue;
+                }
+            }
+            for c in line.chars() {
+                match (in_comment, c) {
+                    (false, ';') => break,
+                    (false, '{') => in_comment = true,
+                    (true, '}') => in_comment = false,
+                    _ => (),
+                }
+            }
+            movetext.push_str(&line);
+            movetext.push('\n');
+        }
+        match tags.is_empty() && movetext.trim().is_empty() {
+            true => Ok(None),
+            false => Ok(Some((tags, movetext))),
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for PgnReader<R> {
+    type Item = Result<PgnGame, PgnGameError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.game += 1;
+        let game = self.game;
+        match self.read_game_text() {
+            Err(e) => Some(Err(PgnGameError { game, ply: 0, error: PgnError::Io(e) })),
+            Ok(None) => None,
+            Ok(Some((tags, movetext))) => Some(parse_game(&tags, &movetext)
+                .map_err(|(ply, error)| PgnGameError { game, ply, error })),
+        }
+    }
+}
+
+pub fn parse_pgn(text: &str) -> Vec<Result<PgnGame, PgnGameError>> {
+    PgnReader::new(text.as_bytes()).collect()
+}
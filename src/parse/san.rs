@@ -0,0 +1,172 @@
+// Standard algebraic notation
+
+use {
+    crate::{
+        board::{
+            color::Color::*,
+            line::*,
+            piece::{*, GenericPiece::*},
+            square::*,
+            zone::FileSide::*,
+        },
+        game::{
+            board_move::*,
+            move_gen::*,
+            position::*,
+        },
+    },
+    super::chr::*,
+};
+
+
+#[derive(Debug)]
+pub enum SanError {
+    MalformedSan,
+    ConversionError(ConversionError),
+    NoMatchingMove,
+    AmbiguousMove,
+}
+
+
+fn converts<T>(result: Result<T, ConversionError>) -> Result<T, SanError> {
+    match result {
+        Err(e) => Err(SanError::ConversionError(e)),
+        Ok(r) => Ok(r),
+    }
+}
+
+fn legal_moves(state: &GameState) -> Vec<Move> {
+    let mut state = state.clone();
+    let mut moves = [Move(0); MAX_LEGAL_MOVES];
+    let n = state.generate_legal_moves(&mut moves);
+    moves[..n].to_vec()
+}
+
+fn expect_one(mut candidates: impl Iterator<Item = Move>) -> Result<Move, SanError> {
+    match (candidates.next(), candidates.next()) {
+        (None, _) => Err(SanError::NoMatchingMove),
+        (Some(mv), None) => Ok(mv),
+        (Some(_), Some(_)) => Err(SanError::AmbiguousMove),
+    }
+}
+
+impl Move {
+
+    pub fn san(self, state: &GameState) -> String {
+        // state is the position before the move is played
+        let mut s = String::new();
+        let piece = self.origin_piece().as_generic();
+        let origin = self.origin_square();
+        let destination = self.destination_square();
+
+        if self.is_castling() {
+            s.push_str(match self.get_castling().file_side() {
+                Kingside => "O-O",
+                Queenside => "O-O-O",
+            });
+        } else {
+            match piece {
+                Pawn => if self.is_capture() {
+                    s.push(origin.file().chr());
+                },
+                _ => {
+                    s.push(piece.as_color(White).chr());
+                    let rivals: Vec<Square> = legal_moves(state).into_iter()
+                        .filter(|mv| mv.origin_piece() == self.origin_piece()
+                            && mv.destination_square() == destination
+                            && mv.origin_square() != origin)
+                        .map(|mv| mv.origin_square())
+                        .collect();
+                    let same_file = rivals.iter().any(|sq| sq.file() == origin.file());
+                    let same_rank = rivals.iter().any(|sq| sq.rank() == origin.rank());
+                    match (rivals.is_empty(), same_file, same_rank) {
+                        (true, _, _) => (),
+                        (false, false, _) => s.push(origin.file().chr()),
+                        (false, true, false) => s.push(origin.rank().chr()),
+                        (false, true, true) => s.extend(origin.chrs()),
+                    }
+                },
+            }
+            if self.is_capture() {
+                s.push('x');
+            }
+            s.extend(destination.chrs());
+            if let Some(p) = self.promotion() {
+                s.push('=');
+                s.push(p.chr().to_ascii_uppercase());
+            }
+        }
+
+        let mut after = state.clone();
+        after.push(self);
+        if after.in_check(after.turn()) {
+            s.push(match legal_moves(&after).is_empty() {
+                true => '#',
+                false => '+',
+            });
+        }
+        s
+    }
+
+    pub fn from_san(state: &GameState, s: &str) -> Result<Move, SanError> {
+        // Accepts 0-0, missing or extra capture marks, e8Q and trailing annotations
+        let san = s.trim().trim_end_matches(['!', '?', '+', '#']);
+        let moves = legal_moves(state);
+
+        let castling = match san {
+            "O-O" | "0-0" => Some(Kingside),
+            "O-O-O" | "0-0-0" => Some(Queenside),
+            _ => None,
+        };
+        if let Some(side) = castling {
+            return expect_one(moves.into_iter()
+                .filter(|mv| mv.is_castling() && mv.get_castling().file_side() == side));
+        }
+
+        let mut chars: Vec<char> = san.chars().collect();
+        let piece = match chars.first() {
+            Some(&c) if "RNBQK".contains(c) => {
+                chars.remove(0);
+                converts(Piece::from_chr(c))?.as_generic()
+            },
+            _ => Pawn,
+        };
+        let promotion = match chars.last() {
+            Some(c) if c.is_ascii_alphabetic() => {
+                let p = converts(Promotion::from_chr(c.to_ascii_lowercase()))?;
+                chars.pop();
+                if chars.last() == Some(&'=') {
+                    chars.pop();
+                }
+                Some(p)
+            },
+            _ => None,
+        };
+        if chars.len() < 2 {
+            return Err(SanError::MalformedSan);
+        }
+        let rank = chars.pop().unwrap();
+        let file = chars.pop().unwrap();
+        let destination = converts(Square::from_chrs(file, rank))?;
+        if let Some('x' | ':' | '-') = chars.last() {
+            chars.pop();
+        }
+
+        let mut from_file: Option<File> = None;
+        let mut from_rank: Option<Rank> = None;
+        for c in chars {
+            match (File::from_chr(c), Rank::from_chr(c)) {
+                (Ok(f), _) if from_file.is_none() => from_file = Some(f),
+                (_, Ok(r)) if from_rank.is_none() => from_rank = Some(r),
+                _ => return Err(SanError::MalformedSan),
+            }
+        }
+
+        expect_one(moves.into_iter().filter(|mv| mv.origin_piece().as_generic() == piece
+            && mv.destination_square() == destination
+            && mv.promotion() == promotion
+            && !mv.is_castling()
+            && from_file.is_none_or(|f| mv.origin_square().file() == f)
+            && from_rank.is_none_or(|r| mv.origin_square().rank() == r)))
+    }
+}
@@ -1,28 +1,172 @@
 // https://www.chessprogramming.org/Perft
 // https://www.chessprogramming.org/Perft_Results
 
+pub mod bench;
+pub mod divide;
+pub mod epd;
+pub mod hashed;
+pub mod parallel;
 
-#[derive(Debug)]
+use {
+    std::time::Instant,
+    crate::{
+        game::{
+            board_move::*,
+            move_gen::*,
+            position::*,
+        },
+        parse::fen::FenError,
+    },
+};
+
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct PerftCounts {
-    nodes: i64,
-    captures: i64,
-    en_passants: i64,
-    castles: i64,
-    promotions: i64,
-    checks: i64,
-    discovered_checks: i64,
-    double_checks: i64,
-    checkmates: i64,
+    pub nodes: i64,
+    pub captures: i64,
+    pub en_passants: i64,
+    pub castles: i64,
+    pub promotions: i64,
+    pub checks: i64,
+    pub discovered_checks: i64,
+    pub double_checks: i64,
+    pub checkmates: i64,
+}
+
+impl PerftCounts {
+
+    pub const FIELDS: [&'static str; 9] = [
+        "nodes",
+        "captures",
+        "en_passants",
+        "castles",
+        "promotions",
+        "checks",
+        "discovered_checks",
+        "double_checks",
+        "checkmates",
+    ];
+
+    pub const fn from_array(arr: [i64; 9]) -> PerftCounts {
+        PerftCounts {
+            nodes: arr[0],
+            captures: arr[1],
+            en_passants: arr[2],
+            castles: arr[3],
+            promotions: arr[4],
+            checks: arr[5],
+            discovered_checks: arr[6],
+            double_checks: arr[7],
+            checkmates: arr[8],
+        }
+    }
+
+    pub const fn to_array(&self) -> [i64; 9] {
+        [
+            self.nodes,
+            self.captures,
+            self.en_passants,
+            self.castles,
+            self.promotions,
+            self.checks,
+            self.discovered_checks,
+            self.double_checks,
+            self.checkmates,
+        ]
+    }
+
+    pub fn add(&mut self, other: &PerftCounts) {
+        let mut arr = self.to_array();
+        for (a, b) in arr.iter_mut().zip(other.to_array()) {
+            *a += b;
+        }
+        *self = PerftCounts::from_array(arr);
+    }
+
+    pub fn matches(&self, expected: &[i64; 9]) -> bool {
+        // Unknown expected values are -1 and always match
+        self.to_array().iter()
+            .zip(expected)
+            .all(|(actual, &expected)| expected == -1 || *actual == expected)
+    }
+
+    fn tally(&mut self, state: &mut GameState, mv: Move) {
+        // Called on the position after mv has been pushed
+        self.nodes += 1;
+        self.captures += mv.is_capture() as i64;
+        self.en_passants += mv.is_ep_capture() as i64;
+        self.castles += mv.is_castling() as i64;
+        self.promotions += mv.is_promotion() as i64;
+
+        let checkers = state.checkers(state.turn());
+        if checkers == 0 {
+            return;
+        }
+        let mut moved = mv.destination_square().mask();
+        if mv.is_castling() {
+            moved |= mv.get_castling().rook_end().mask();
+        }
+        self.checks += 1;
+        self.discovered_checks += (checkers & moved == 0) as i64;
+        self.double_checks += (checkers.count_ones() > 1) as i64;
+
+        let mut moves = [Move(0); MAX_LEGAL_MOVES];
+        self.checkmates += (state.generate_legal_moves(&mut moves) == 0) as i64;
+    }
 }
 
+
 #[derive(Debug)]
 pub struct PerftResult {
-    fen: &'static str,
-    depth: u64,
-    seconds: f64,
-    counts: PerftCounts,
+    pub fen: &'static str,
+    pub depth: u64,
+    pub seconds: f64,
+    pub counts: PerftCounts,
+}
+
+impl PerftResult {
+
+    pub fn run(fen: &'static str, depth: u64) -> Result<PerftResult, FenError> {
+        let mut state = GameState::from_fen(fen)?;
+        let start = Instant::now();
+        let counts = perft(&mut state, depth);
+        Ok(PerftResult {
+            fen,
+            depth,
+            seconds: start.elapsed().as_secs_f64(),
+            counts,
+        })
+    }
+
+    pub fn nps(&self) -> f64 {
+        self.counts.nodes as f64 / self.seconds
+    }
 }
 
+
+pub fn perft(state: &mut GameState, depth: u64) -> PerftCounts {
+    let mut counts = PerftCounts::default();
+    match depth {
+        0 => counts.nodes = 1,
+        _ => perft_recurse(state, depth, &mut counts),
+    }
+    counts
+}
+
+fn perft_recurse(state: &mut GameState, depth: u64, counts: &mut PerftCounts) {
+    let mut moves = [Move(0); MAX_LEGAL_MOVES];
+    let n = state.generate_legal_moves(&mut moves);
+    for &mv in &moves[..n] {
+        let undo = state.push(mv);
+        match depth {
+            1 => counts.tally(state, mv),
+            _ => perft_recurse(state, depth - 1, counts),
+        }
+        state.pop(mv, undo);
+    }
+}
+
+
 pub const FENS: [&str; 7] = [
     "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
     "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",  // Kiwipete
@@ -35,7 +179,7 @@ pub const FENS: [&str; 7] = [
 
 pub const EXPECTED: [(&str, u64, [i64; 9]); 55] = [
                                     // nodes         captures  en_passants        castles  promotions          checks  discovered     double   checkmates
-    (FENS[0], 0,  [                        0,               0,           0,             0,          0,              0,          0,         0,           0]),
+    (FENS[0], 0,  [                        1,               0,           0,             0,          0,              0,          0,         0,           0]),
     (FENS[0], 1,  [                       20,               0,           0,             0,          0,              0,          0,         0,           0]),
     (FENS[0], 2,  [                      400,               0,           0,             0,          0,              0,          0,         0,           0]),
     (FENS[0], 3,  [                    8_902,              34,           0,             0,          0,             12,          0,         0,           0]),
@@ -52,12 +196,14 @@ pub const EXPECTED: [(&str, u64, [i64; 9]); 55] = [
     // (FENS[0], 14, [61_885_021_521_585_529_237, -1, -1, -1, -1, -1, -1, -1, -1]),
     // (FENS[0], 15, [201_509_995_0053_364_471_960, -1, -1, -1, -1, -1, -1, -1, -1]),
     
-    // The number of double-checks in depth 5 is discussed in Talkchess [8] and may be 2645 instead of 2637.
+    // The wiki lists 2637 double checks at depth 5 and cites a Talkchess thread for 2645.
+    // Here a double check is any move after which two or more pieces attack the king,
+    // whatever piece moved, which gives 2645.
     (FENS[1], 1, [           48,             8,         0,           2,          0,          0,       0,      0,       0]),
     (FENS[1], 2, [        2_039,           351,         1,          91,          0,          3,       0,      0,       0]),
     (FENS[1], 3, [       97_862,        17_102,        45,       3_162,          0,        993,       0,      0,       1]),
     (FENS[1], 4, [    4_085_603,       757_163,     1_929,     128_013,     15_172,     25_523,      42,      6,      43]),
-    (FENS[1], 5, [  193_690_690,    35_043_416,    73_365,   4_993_637,      8_392,  3_309_887,  19_883,  2_637,  30_171]),
+    (FENS[1], 5, [  193_690_690,    35_043_416,    73_365,   4_993_637,      8_392,  3_309_887,  19_883,  2_645,  30_171]),
     (FENS[1], 6, [8_031_647_685, 1_558_445_089, 3_577_504, 184_513_607, 56_627_920, 92_238_050, 568_417, 54_948, 360_003]),
 
     (FENS[2], 1, [           14,           1,         0, 0,         0,           2,         0,     0,       0]),
@@ -66,7 +212,7 @@ pub const EXPECTED: [(&str, u64, [i64; 9]); 55] = [
     (FENS[2], 4, [       43_238,       3_348,       123, 0,         0,       1_680,       106,     0,      17]),
     (FENS[2], 5, [      674_624,      52_051,     1_165, 0,         0,      52_950,     1_292,     3,       0]),
     (FENS[2], 6, [   11_030_083,     940_350,    33_325, 0,     7_552,     452_473,    26_067,     0,   2_733]),
-    (FENS[2], 7, [  178_633_661,  14_519_036,   294_874, 0,   140_024,  12_797_406,    37_630, 3_612,      87]),
+    (FENS[2], 7, [  178_633_661,  14_519_036,   294_874, 0,   140_024,  12_797_406,   370_630, 3_612,      87]),
     (FENS[2], 8, [3_009_794_393, 267_586_558, 8_009_239, 0, 6_578_076, 135_626_805, 7_181_487, 1_630, 450_410]),
 
     (FENS[3], 1, [          6,           0,     0,          0,          0,          0, -1, -1,      0]),
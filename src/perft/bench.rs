@@ -0,0 +1,82 @@
+// Times the legal move generators against each other over the perft suite
+
+use {
+    std::time::Instant,
+    crate::game::{
+        board_move::*,
+        move_gen::*,
+        position::*,
+    },
+    super::*,
+};
+
+
+pub type Generator = fn(&mut GameState, &mut [Move; MAX_LEGAL_MOVES]) -> usize;
+
+pub const GENERATORS: [(&str, Generator); 2] = [
+    ("pins", GameState::generate_legal_moves),
+    ("make/unmake", GameState::generate_legal_moves_by_make_unmake),
+];
+
+
+#[derive(Debug)]
+pub struct BenchResult {
+    pub fen: &'static str,
+    pub depth: u64,
+    pub expected: u64,
+    // In the order of GENERATORS
+    pub nodes: [u64; GENERATORS.len()],
+    pub seconds: [f64; GENERATORS.len()],
+}
+
+impl BenchResult {
+
+    pub fn passed(&self) -> bool {
+        self.nodes.iter().all(|&n| n == self.expected)
+    }
+
+    pub fn speedup(&self) -> f64 {
+        // Of the first generator over the second
+        self.seconds[1] / self.seconds[0]
+    }
+}
+
+
+pub fn count_nodes(state: &mut GameState, depth: u64, generator: Generator) -> u64 {
+    // Plain perft: leaf nodes only, counted in bulk at the last ply
+    let mut moves = [Move(0); MAX_LEGAL_MOVES];
+    let n = generator(state, &mut moves);
+    if depth <= 1 {
+        return match depth {
+            0 => 1,
+            _ => n as u64,
+        };
+    }
+    let mut nodes = 0;
+    for &mv in &moves[..n] {
+        let undo = state.push(mv);
+        nodes += count_nodes(state, depth - 1, generator);
+        state.pop(mv, undo);
+    }
+    nodes
+}
+
+pub fn bench_generators(max_depth: u64) -> Vec<BenchResult> {
+    // Every EXPECTED row up to the depth, each generator timed on the same tree
+    let mut results = Vec::new();
+    for (fen, depth, expected) in EXPECTED {
+        if depth > max_depth {
+            continue;
+        }
+        let mut state = GameState::from_fen(fen).unwrap();
+        let mut nodes = [0; GENERATORS.len()];
+        let mut seconds = [0.0; GENERATORS.len()];
+        for (i, (_, generator)) in GENERATORS.iter().enumerate() {
+            let start = Instant::now();
+            nodes[i] = count_nodes(&mut state, depth, *generator);
+            seconds[i] = start.elapsed().as_secs_f64();
+        }
+        results.push(BenchResult { fen, depth, expected: expected[0] as u64, nodes, seconds });
+    }
+    results
+}
@@ -0,0 +1,209 @@
+// Divide: perft split by root move, for locating move generation bugs
+// https://www.chessprogramming.org/Perft#Divide
+
+use {
+    std::{
+        collections::HashMap,
+        sync::atomic::{AtomicBool, Ordering},
+    },
+    crate::{
+        board::{
+            line::*,
+            piece::Promotion,
+        },
+        game::{
+            board_move::*,
+            move_gen::*,
+            position::*,
+        },
+    },
+    super::*,
+};
+
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum DivideMismatch {
+    MissingMove(String),  // Listed by the reference but not generated
+    ExtraMove(String),  // Generated but not listed by the reference
+    NodeCount(String, i64, i64),  // Move, expected, actual
+}
+
+#[derive(Debug)]
+pub struct Divergence {
+    pub path: Vec<Move>,  // Moves played from the root to reach fen
+    pub fen: String,
+    pub depth: u64,
+    pub mismatch: DivideMismatch,
+}
+
+
+pub fn divide(state: &mut GameState, depth: u64) -> Vec<(Move, i64)> {
+    let mut result = Vec::new();
+    if depth == 0 {
+        return result;
+    }
+    let mut moves = [Move(0); MAX_LEGAL_MOVES];
+    let n = state.generate_legal_moves(&mut moves);
+    for &mv in &moves[..n] {
+        let undo = state.push(mv);
+        result.push((mv, perft(state, depth - 1).nodes));
+        state.pop(mv, undo);
+    }
+    result
+}
+
+fn count_until(state: &mut GameState, depth: u64, stop: &AtomicBool) -> Option<i64> {
+    // Leaf nodes, counted in bulk at the last ply, or None once stop is set
+    if stop.load(Ordering::Relaxed) {
+        return None;
+    }
+    let mut moves = [Move(0); MAX_LEGAL_MOVES];
+    let n = state.generate_legal_moves(&mut moves);
+    if depth <= 1 {
+        return Some(match depth {
+            0 => 1,
+            _ => n as i64,
+        });
+    }
+    let mut nodes = 0;
+    for &mv in &moves[..n] {
+        let undo = state.push(mv);
+        let count = count_until(state, depth - 1, stop);
+        state.pop(mv, undo);
+        nodes += count?;
+    }
+    Some(nodes)
+}
+
+pub fn divide_until(state: &mut GameState, depth: u64, stop: &AtomicBool) -> Option<Vec<(Move, i64)>> {
+    // Node counts only, and abandoned as soon as stop is set
+    let mut result = Vec::new();
+    if depth == 0 {
+        return Some(result);
+    }
+    let mut moves = [Move(0); MAX_LEGAL_MOVES];
+    let n = state.generate_legal_moves(&mut moves);
+    for &mv in &moves[..n] {
+        let undo = state.push(mv);
+        let count = count_until(state, depth - 1, stop);
+        state.pop(mv, undo);
+        result.push((mv, count?));
+    }
+    Some(result)
+}
+
+pub fn print_divide(divide: &[(Move, i64)]) {
+    // Same layout as Stockfish's "go perft" so listings can be diffed
+    for (mv, nodes) in divide {
+        println!("{}: {}", mv.pacn(), nodes);
+    }
+    println!();
+    println!("Nodes searched: {}", divide.iter().map(|(_, n)| n).sum::<i64>());
+}
+
+
+fn is_pacn(s: &str) -> bool {
+    let chars: Vec<char> = s.chars().collect();
+    let square = |f: char, r: char| File::from_chr(f).is_ok() && Rank::from_chr(r).is_ok();
+    match chars[..] {
+        [f1, r1, f2, r2] => square(f1, r1) && square(f2, r2),
+        [f1, r1, f2, r2, p] => square(f1, r1) && square(f2, r2) && Promotion::from_chr(p).is_ok(),
+        _ => false,
+    }
+}
+
+pub fn parse_divide(listing: &str) -> HashMap<String, i64> {
+    // Accepts "e2e4: 20" and "e2e4 20" lines; anything else is skipped
+    let mut result = HashMap::new();
+    for line in listing.lines() {
+        let tokens: Vec<&str> = line
+            .split(|c: char| c == ':' || c.is_whitespace())
+            .filter(|t| !t.is_empty())
+            .collect();
+        if let [mv, nodes] = tokens[..] {
+            let mv = mv.to_lowercase();
+            if let (true, Ok(n)) = (is_pacn(&mv), nodes.parse::<i64>()) {
+                result.insert(mv, n);
+            }
+        }
+    }
+    result
+}
+
+
+pub fn find_divergence<F>(
+    state: &mut GameState,
+    depth: u64,
+    reference: &str,
+    mut next_reference: F,
+) -> Option<Divergence>
+where
+    F: FnMut(&GameState, &[Move], u64) -> String,
+{
+    // next_reference is asked for the reference divide of each child
+    // position that is descended into, given the moves from the root.
+    let mut path = Vec::new();
+    descend(state, depth, reference, &mut path, &mut next_reference)
+}
+
+fn diverged(
+    state: &GameState,
+    depth: u64,
+    path: &[Move],
+    mismatch: DivideMismatch,
+) -> Option<Divergence> {
+    Some(Divergence {
+        path: path.to_vec(),
+        fen: state.fen(),
+        depth,
+        mismatch,
+    })
+}
+
+fn descend<F>(
+    state: &mut GameState,
+    depth: u64,
+    reference: &str,
+    path: &mut Vec<Move>,
+    next_reference: &mut F,
+) -> Option<Divergence>
+where
+    F: FnMut(&GameState, &[Move], u64) -> String,
+{
+    let expected = parse_divide(reference);
+    let actual = divide(state, depth);
+    for (mv, _) in &actual {
+        if !expected.contains_key(&mv.pacn()) {
+            return diverged(state, depth, path, DivideMismatch::ExtraMove(mv.pacn()));
+        }
+    }
+    let mut missing: Vec<&String> = expected.keys()
+        .filter(|s| !actual.iter().any(|(mv, _)| mv.pacn() == **s))
+        .collect();
+    missing.sort();
+    if let Some(s) = missing.first() {
+        return diverged(state, depth, path, DivideMismatch::MissingMove(s.to_string()));
+    }
+
+    for &(mv, nodes) in &actual {
+        let expected_nodes = expected[&mv.pacn()];
+        if expected_nodes == nodes {
+            continue;
+        }
+        if depth > 1 {
+            let undo = state.push(mv);
+            path.push(mv);
+            let child_reference = next_reference(state, path, depth - 1);
+            let found = descend(state, depth - 1, &child_reference, path, next_reference);
+            path.pop();
+            state.pop(mv, undo);
+            if found.is_some() {
+                return found;
+            }
+        }
+        // The children agree with the reference, so the fault is in this move
+        return diverged(
+            state, depth, path, DivideMismatch::NodeCount(mv.pacn(), expected_nodes, nodes));
+    }
+    None
+}
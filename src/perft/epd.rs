@@ -0,0 +1,141 @@
+// Perft suites in the EPD layout of perftsuite.epd:
+// <fen> ;D1 20 ;D2 400 ;D3 8902
+
+use {
+    std::time::Instant,
+    crate::{
+        game::position::*,
+        parse::fen::FenError,
+    },
+    super::parallel::*,
+};
+
+
+#[derive(Debug)]
+pub enum EpdError {
+    MissingFen,
+    InvalidFen(FenError),
+    MalformedDepth(String),  // The offending ";D<depth> <nodes>" field
+}
+
+#[derive(Debug)]
+pub struct EpdLineError {
+    pub line: usize,  // 1-based
+    pub error: EpdError,
+}
+
+#[derive(Debug, Clone)]
+pub struct PerftCase {
+    pub line: usize,
+    pub fen: String,
+    pub depth: u64,
+    pub nodes: i64,
+}
+
+#[derive(Debug)]
+pub struct PerftCaseResult {
+    pub case: PerftCase,
+    pub nodes: i64,
+    pub seconds: f64,
+}
+
+impl PerftCaseResult {
+
+    pub fn passed(&self) -> bool {
+        self.nodes == self.case.nodes
+    }
+}
+
+
+fn complete_fen(fen: &str) -> String {
+    // Many suites omit the move counters
+    match fen.split_whitespace().count() {
+        4 => format!("{} 0 1", fen),
+        _ => String::from(fen),
+    }
+}
+
+fn parse_depth(field: &str) -> Result<(u64, i64), EpdError> {
+    let malformed = || EpdError::MalformedDepth(String::from(field));
+    let mut tokens = field.split_whitespace();
+    let depth = match tokens.next().and_then(|t| t.strip_prefix('D')) {
+        Some(d) => d.parse::<u64>().map_err(|_| malformed())?,
+        None => return Err(malformed()),
+    };
+    let nodes = match tokens.next() {
+        Some(n) => n.parse::<i64>().map_err(|_| malformed())?,
+        None => return Err(malformed()),
+    };
+    match tokens.next() {
+        Some(_) => Err(malformed()),
+        None => Ok((depth, nodes)),
+    }
+}
+
+pub fn parse_epd_line(line: &str) -> Result<(String, Vec<(u64, i64)>), EpdError> {
+    let mut fields = line.split(';');
+    let fen = match fields.next().map(str::trim) {
+        None | Some("") => return Err(EpdError::MissingFen),
+        Some(fen) => complete_fen(fen),
+    };
+    if let Err(e) = GameState::from_fen(&fen) {
+        return Err(EpdError::InvalidFen(e));
+    }
+    let depths = fields
+        .map(str::trim)
+        .filter(|f| !f.is_empty())
+        .map(parse_depth)
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((fen, depths))
+}
+
+pub fn parse_epd(text: &str) -> (Vec<PerftCase>, Vec<EpdLineError>) {
+    // Malformed lines are collected rather than ending the parse
+    let mut cases = Vec::new();
+    let mut errors = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line_number = i + 1;
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        match parse_epd_line(line) {
+            Err(error) => errors.push(EpdLineError { line: line_number, error }),
+            Ok((fen, depths)) => for (depth, nodes) in depths {
+                cases.push(PerftCase {
+                    line: line_number,
+                    fen: fen.clone(),
+                    depth,
+                    nodes,
+                });
+            },
+        }
+    }
+    (cases, errors)
+}
+
+
+pub fn run_case(case: &PerftCase, threads: usize) -> PerftCaseResult {
+    // FENs were validated when the suite was parsed
+    let state = GameState::from_fen(&case.fen).unwrap();
+    let start = Instant::now();
+    let nodes = parallel_perft(&state, case.depth, threads).nodes;
+    PerftCaseResult {
+        case: case.clone(),
+        nodes,
+        seconds: start.elapsed().as_secs_f64(),
+    }
+}
+
+pub fn run_epd<F>(cases: &[PerftCase], max_depth: u64, threads: usize, mut report: F) -> Vec<PerftCaseResult>
+where
+    F: FnMut(&PerftCaseResult),
+{
+    // report is called after each case so long suites show progress
+    let mut results = Vec::new();
+    for case in cases.iter().filter(|c| c.depth <= max_depth) {
+        let result = run_case(case, threads);
+        report(&result);
+        results.push(result);
+    }
+    results
+}
@@ -0,0 +1,162 @@
+// Perft with a transposition cache of subtree counts, keyed on the
+// Zobrist hash and the remaining depth.
+
+use {
+    std::mem::size_of,
+    crate::game::{
+        board_move::*,
+        move_gen::*,
+        position::*,
+    },
+    super::*,
+};
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Replacement {
+    Always,
+    DepthPreferred,  // Keep the entry covering the larger subtree
+}
+
+#[derive(Debug)]
+pub struct HashCollision {
+    pub fen: String,
+    pub depth: u64,
+    pub cached: PerftCounts,
+    pub actual: PerftCounts,
+}
+
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Entry {
+    key: u64,
+    depth: u64,  // 0 marks an empty slot; depth 0 is never stored
+    counts: PerftCounts,
+}
+
+pub struct PerftCache {
+    entries: Vec<Entry>,
+    replacement: Replacement,
+    pub probes: u64,
+    pub hits: u64,
+}
+
+impl PerftCache {
+
+    pub fn new(megabytes: usize, replacement: Replacement) -> PerftCache {
+        PerftCache::with_capacity(megabytes * 1024 * 1024 / size_of::<Entry>(), replacement)
+    }
+
+    pub fn with_capacity(entries: usize, replacement: Replacement) -> PerftCache {
+        // Rounded down to a power of two so the index is a mask
+        let entries = match entries {
+            0 => 1,
+            n => 1 << n.ilog2(),
+        };
+        PerftCache {
+            entries: vec![Entry::default(); entries],
+            replacement,
+            probes: 0,
+            hits: 0,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.fill(Entry::default());
+        self.probes = 0;
+        self.hits = 0;
+    }
+
+    const fn index(&self, key: u64, depth: u64) -> usize {
+        // Spread the depths of one position over different slots
+        (key ^ depth.wrapping_mul(0x9e3779b97f4a7c15)) as usize & (self.entries.len() - 1)
+    }
+
+    pub fn probe(&mut self, key: u64, depth: u64) -> Option<PerftCounts> {
+        self.probes += 1;
+        let entry = &self.entries[self.index(key, depth)];
+        match entry.key == key && entry.depth == depth {
+            true => {
+                self.hits += 1;
+                Some(entry.counts)
+            },
+            false => None,
+        }
+    }
+
+    pub fn store(&mut self, key: u64, depth: u64, counts: PerftCounts) {
+        let i = self.index(key, depth);
+        let replace = match self.replacement {
+            Replacement::Always => true,
+            Replacement::DepthPreferred => depth >= self.entries[i].depth,
+        };
+        if replace {
+            self.entries[i] = Entry { key, depth, counts };
+        }
+    }
+}
+
+
+pub fn hashed_perft(state: &mut GameState, depth: u64, cache: &mut PerftCache) -> PerftCounts {
+    match recurse(state, depth, cache, false) {
+        Ok(counts) => counts,
+        Err(_) => unreachable!("collisions are only checked when verifying"),
+    }
+}
+
+pub fn verified_hashed_perft(
+    state: &mut GameState,
+    depth: u64,
+    cache: &mut PerftCache,
+) -> Result<PerftCounts, Box<HashCollision>> {
+    // Every cache hit is checked against an uncached perft of the position
+    recurse(state, depth, cache, true)
+}
+
+fn recurse(
+    state: &mut GameState,
+    depth: u64,
+    cache: &mut PerftCache,
+    verify: bool,
+) -> Result<PerftCounts, Box<HashCollision>> {
+    if depth == 0 {
+        return Ok(perft(state, 0));
+    }
+    if let Some(cached) = cache.probe(state.zobrist_hash, depth) {
+        if !verify {
+            return Ok(cached);
+        }
+        let actual = perft(state, depth);
+        return match actual == cached {
+            true => Ok(cached),
+            false => Err(Box::new(HashCollision {
+                fen: state.fen(),
+                depth,
+                cached,
+                actual,
+            })),
+        };
+    }
+
+    let mut counts = PerftCounts::default();
+    let mut moves = [Move(0); MAX_LEGAL_MOVES];
+    let n = state.generate_legal_moves(&mut moves);
+    for &mv in &moves[..n] {
+        let undo = state.push(mv);
+        let result = match depth {
+            1 => {
+                counts.tally(state, mv);
+                Ok(())
+            },
+            _ => recurse(state, depth - 1, cache, verify).map(|c| counts.add(&c)),
+        };
+        state.pop(mv, undo);
+        result?;
+    }
+    cache.store(state.zobrist_hash, depth, counts);
+    Ok(counts)
+}
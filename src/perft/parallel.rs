@@ -0,0 +1,117 @@
+// Perft split across worker threads. The tree below the first one or two
+// plies is divided into work items, dealt out to per-worker queues, and
+// idle workers steal from the back of the other queues.
+
+use {
+    std::{
+        collections::VecDeque,
+        sync::Mutex,
+        thread,
+        time::Instant,
+    },
+    crate::{
+        game::{
+            board_move::*,
+            move_gen::*,
+            position::*,
+        },
+        parse::fen::FenError,
+    },
+    super::*,
+};
+
+
+pub fn default_threads() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+fn split(state: &mut GameState, plies: u64, path: &mut Vec<Move>, items: &mut Vec<Vec<Move>>) {
+    if plies == 0 {
+        items.push(path.clone());
+        return;
+    }
+    let mut moves = [Move(0); MAX_LEGAL_MOVES];
+    let n = state.generate_legal_moves(&mut moves);
+    for &mv in &moves[..n] {
+        let undo = state.push(mv);
+        path.push(mv);
+        split(state, plies - 1, path, items);
+        path.pop();
+        state.pop(mv, undo);
+    }
+}
+
+fn next_item(queues: &[Mutex<VecDeque<Vec<Move>>>], own: usize) -> Option<Vec<Move>> {
+    if let Some(item) = queues[own].lock().unwrap().pop_front() {
+        return Some(item);
+    }
+    (1..queues.len())
+        .map(|i| (own + i) % queues.len())
+        .find_map(|i| queues[i].lock().unwrap().pop_back())
+}
+
+fn worker(root: &GameState, depth: u64, queues: &[Mutex<VecDeque<Vec<Move>>>], own: usize) -> PerftCounts {
+    let mut counts = PerftCounts::default();
+    while let Some(path) = next_item(queues, own) {
+        let mut state = root.clone();
+        for &mv in &path {
+            state.push(mv);
+        }
+        counts.add(&perft(&mut state, depth - path.len() as u64));
+    }
+    counts
+}
+
+pub fn parallel_perft(state: &GameState, depth: u64, threads: usize) -> PerftCounts {
+    // Identical to perft for any thread count; threads == 0 uses every core
+    let threads = match threads {
+        0 => default_threads(),
+        n => n,
+    };
+    // Splitting at depth 2 gives around 400 items to balance over
+    let plies = match depth {
+        0..=1 => return perft(&mut state.clone(), depth),
+        2 => 1,
+        _ => 2,
+    };
+    let mut items = Vec::new();
+    split(&mut state.clone(), plies, &mut Vec::new(), &mut items);
+
+    let queues: Vec<Mutex<VecDeque<Vec<Move>>>> = (0..threads)
+        .map(|_| Mutex::new(VecDeque::new()))
+        .collect();
+    for (i, item) in items.into_iter().enumerate() {
+        queues[i % threads].lock().unwrap().push_back(item);
+    }
+
+    let mut counts = PerftCounts::default();
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|own| {
+                let root = state.clone();
+                let queues = &queues;
+                scope.spawn(move || worker(&root, depth, queues, own))
+            })
+            .collect();
+        for handle in handles {
+            counts.add(&handle.join().unwrap());
+        }
+    });
+    counts
+}
+
+
+impl PerftResult {
+
+    pub fn run_parallel(fen: &'static str, depth: u64, threads: usize) -> Result<PerftResult, FenError> {
+        let state = GameState::from_fen(fen)?;
+        let start = Instant::now();
+        let counts = parallel_perft(&state, depth, threads);
+        Ok(PerftResult {
+            fen,
+            depth,
+            seconds: start.elapsed().as_secs_f64(),
+            counts,
+        })
+    }
+}
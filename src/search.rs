@@ -0,0 +1,712 @@
+// Negamax alpha-beta with iterative deepening and quiescence search
+// https://www.chessprogramming.org/Alpha-Beta
+// https://www.chessprogramming.org/Iterative_Deepening
+// https://www.chessprogramming.org/Quiescence_Search
+// https://www.chessprogramming.org/Selectivity
+// https://www.chessprogramming.org/Lazy_SMP
+
+pub mod eval;
+pub mod mate;
+pub mod multipv;
+pub mod ordering;
+pub mod time;
+pub mod tt;
+
+use {
+    std::{
+        sync::{
+            Arc,
+            atomic::{AtomicBool, AtomicU64, Ordering},
+        },
+        thread,
+        time::Duration,
+    },
+    crate::{
+        board::piece::GenericPiece::*,
+        game::{
+            board_move::*,
+            move_gen::*,
+            position::*,
+        },
+    },
+    eval::*,
+    ordering::*,
+    time::*,
+    tt::*,
+};
+
+
+pub const MAX_PLY: usize = 128;
+pub const MATE: i32 = 32_000;
+pub const INFINITY: i32 = MATE + 1;
+pub const DRAW: i32 = 0;
+
+// Nodes between checks of the stop flag and limits
+const CHECK_INTERVAL: u64 = 1024;
+
+// Selectivity margins and thresholds, indexed by remaining depth where arrays
+const NULL_MOVE_MIN_DEPTH: u64 = 3;
+const REVERSE_FUTILITY_MAX_DEPTH: u64 = 6;
+const REVERSE_FUTILITY_MARGIN: i32 = 120;
+const RAZOR_MARGINS: [i32; 3] = [0, 300, 500];
+const FUTILITY_MARGINS: [i32; 4] = [0, 150, 300, 500];
+const LMR_MIN_DEPTH: u64 = 3;
+const LMR_MIN_MOVES: usize = 3;
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Score {
+    Centipawns(i32),
+    Mate(i32),  // In moves, negative when the side to move is being mated
+}
+
+impl Score {
+
+    pub const fn from_value(value: i32) -> Score {
+        match (value >= MATE - MAX_PLY as i32, value <= -MATE + MAX_PLY as i32) {
+            (true, _) => Score::Mate((MATE - value + 1) / 2),
+            (_, true) => Score::Mate(-(MATE + value + 1) / 2),
+            _ => Score::Centipawns(value),
+        }
+    }
+}
+
+
+#[derive(Debug, Clone, Default)]
+pub struct SearchLimits {
+    // None for no limit; the search then runs until stopped
+    pub depth: Option<u64>,
+    pub nodes: Option<u64>,
+    pub time: Option<Duration>,  // Fixed time for the move
+    pub clock: Option<ClockLimits>,  // Time for the move left to the time manager
+}
+
+impl SearchLimits {
+
+    pub fn depth(depth: u64) -> SearchLimits {
+        SearchLimits { depth: Some(depth), ..SearchLimits::default() }
+    }
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchConfig {
+    // Each technique can be switched off to measure what it saves
+    pub null_move: bool,
+    pub late_move_reductions: bool,
+    pub futility: bool,
+    pub reverse_futility: bool,
+    pub razoring: bool,
+    pub check_extensions: bool,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        SearchConfig {
+            null_move: true,
+            late_move_reductions: true,
+            futility: true,
+            reverse_futility: true,
+            razoring: true,
+            check_extensions: true,
+        }
+    }
+}
+
+impl SearchConfig {
+
+    // Full-width alpha-beta with no selectivity at all
+    pub const PLAIN: SearchConfig = SearchConfig {
+        null_move: false,
+        late_move_reductions: false,
+        futility: false,
+        reverse_futility: false,
+        razoring: false,
+        check_extensions: false,
+    };
+}
+
+
+#[derive(Debug, Clone)]
+pub struct SearchInfo {
+    // Result of the last completed iteration
+    pub depth: u64,
+    pub score: i32,
+    // Of all threads, the sum of thread_nodes. Helpers publish their counts after each
+    // of their iterations and every CHECK_INTERVAL nodes, so until the search ends
+    // each may lag by up to that many.
+    pub nodes: u64,
+    pub thread_nodes: Vec<u64>,  // The main thread first, then each helper
+    pub elapsed: Duration,
+    pub pv: Vec<Move>,
+    pub hashfull: u32,  // Permille
+}
+
+impl SearchInfo {
+
+    pub const fn score(&self) -> Score {
+        Score::from_value(self.score)
+    }
+
+    pub fn best_move(&self) -> Option<Move> {
+        self.pv.first().copied()
+    }
+
+    pub fn nps(&self) -> u64 {
+        (self.nodes as f64 / self.elapsed.as_secs_f64().max(1e-9)) as u64
+    }
+}
+
+
+pub struct Searcher {
+    stop: Arc<AtomicBool>,
+    threads: usize,
+    // Helpers only search to fill the shared table; the main thread checks limits and reports
+    helper: bool,
+    // Nodes of every thread of the search, each storing its own in its slot
+    thread_nodes: Arc<Vec<AtomicU64>>,
+    thread_index: usize,
+    limits: SearchLimits,
+    clock: Arc<dyn Clock>,
+    start: Duration,
+    time_manager: Option<TimeManager>,
+    nodes: u64,
+    stopped: bool,
+    // Positions before the current node: game history then the search path
+    hashes: Vec<u64>,
+    // Moves from the root to the current node, None for a null move
+    path: Vec<Option<Move>>,
+    // Principal variation of the previous iteration, tried first at each ply
+    prev_pv: Vec<Move>,
+    // Root moves left out, for the lines after the first in MultiPV
+    excluded: Vec<Move>,
+    tt: Arc<TranspositionTable>,
+    move_history: MoveHistory,
+    config: SearchConfig,
+}
+
+impl Default for Searcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Searcher {
+
+    pub fn new() -> Searcher {
+        Searcher::with_table(Arc::new(TranspositionTable::new(TranspositionTable::DEFAULT_MB)))
+    }
+
+    fn with_table(tt: Arc<TranspositionTable>) -> Searcher {
+        Searcher {
+            stop: Arc::new(AtomicBool::new(false)),
+            threads: 1,
+            helper: false,
+            thread_nodes: Arc::new(vec![AtomicU64::new(0)]),
+            thread_index: 0,
+            limits: SearchLimits::default(),
+            clock: Arc::new(SystemClock::new()),
+            start: Duration::ZERO,
+            time_manager: None,
+            nodes: 0,
+            stopped: false,
+            hashes: Vec::new(),
+            path: Vec::new(),
+            prev_pv: Vec::new(),
+            excluded: Vec::new(),
+            tt,
+            move_history: MoveHistory::new(),
+            config: SearchConfig::default(),
+        }
+    }
+
+    pub fn set_hash_size(&mut self, megabytes: usize) {
+        self.tt = Arc::new(TranspositionTable::new(megabytes));
+    }
+
+    pub fn clear_hash(&mut self) {
+        // Forgets everything learnt from earlier searches
+        self.tt.clear();
+        self.move_history.clear();
+    }
+
+    pub const fn config(&self) -> SearchConfig {
+        self.config
+    }
+
+    pub const fn set_config(&mut self, config: SearchConfig) {
+        self.config = config;
+    }
+
+    pub fn tt(&self) -> &TranspositionTable {
+        &self.tt
+    }
+
+    pub const fn threads(&self) -> usize {
+        self.threads
+    }
+
+    pub fn set_threads(&mut self, threads: usize) {
+        // The main thread counts as one
+        self.threads = threads.max(1);
+    }
+
+    fn helper(&self, thread_index: usize, stop: Arc<AtomicBool>) -> Searcher {
+        Searcher {
+            stop,
+            helper: true,
+            thread_nodes: self.thread_nodes.clone(),
+            thread_index,
+            clock: self.clock.clone(),
+            config: self.config,
+            ..Searcher::with_table(self.tt.clone())
+        }
+    }
+
+    fn publish_nodes(&self) -> u64 {
+        // Makes this thread's count visible to the others and returns the total
+        self.thread_nodes[self.thread_index].store(self.nodes, Ordering::Relaxed);
+        self.thread_nodes.iter().map(|n| n.load(Ordering::Relaxed)).sum()
+    }
+
+    fn node_counts(&self) -> Vec<u64> {
+        self.thread_nodes.iter().map(|n| n.load(Ordering::Relaxed)).collect()
+    }
+
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        // The time source for every limit; tests swap in a ManualClock
+        self.clock = clock;
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.clock.now().saturating_sub(self.start)
+    }
+
+    pub fn stop_flag(&self) -> Arc<AtomicBool> {
+        // Setting the flag ends the search. It is not cleared by search,
+        // so the caller resets it before starting the next one.
+        self.stop.clone()
+    }
+
+    fn out_of_limits(&self) -> bool {
+        let nodes = self.publish_nodes();
+        if self.stop.load(Ordering::Relaxed) || self.limits.nodes.is_some_and(|n| nodes >= n) {
+            return true;
+        }
+        let deadline = match (self.limits.time, &self.time_manager) {
+            (Some(time), _) => time,
+            (None, Some(tm)) => tm.hard_deadline(),
+            (None, None) => return false,
+        };
+        self.elapsed() >= deadline
+    }
+
+    fn poll(&mut self) {
+        // The first iteration of the main thread always completes so that there is a move to play
+        if self.nodes.is_multiple_of(CHECK_INTERVAL) && (self.helper || !self.prev_pv.is_empty())
+            && self.out_of_limits() {
+            self.stopped = true;
+        }
+    }
+
+    fn is_repetition(&self, state: &GameState) -> bool {
+        // Only positions since the last capture or pawn move can repeat
+        let n = self.hashes.len();
+        let reversible = (state.halfmove_ctr() as usize).min(n);
+        self.hashes[n - reversible..].iter().rev().skip(1).step_by(2)
+            .any(|&hash| hash == state.zobrist_hash)
+    }
+
+    fn quiescence(&mut self, state: &mut GameState, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        // Captures and promotions until the position is quiet. In check every
+        // evasion is searched, since standing pat is not an option.
+        self.poll();
+        if self.stopped {
+            return DRAW;
+        }
+
+        if ply >= MAX_PLY - 1 {
+            return evaluate(state);
+        }
+
+        let mut moves = [Move(0); MAX_LEGAL_MOVES];
+        let mut n;
+        let mut best = -INFINITY;
+        match state.in_check(state.turn()) {
+            true => {
+                n = state.generate_evasions(&mut moves);
+                if n == 0 {
+                    return -MATE + ply as i32;
+                }
+            },
+            false => {
+                // Stalemates are left to the main search
+                best = evaluate(state);
+                if best >= beta {
+                    return best;
+                }
+                alpha = alpha.max(best);
+                n = state.generate_captures(&mut moves);
+                // Exchanges that lose material cannot raise the score above standing pat
+                let mut i = 0;
+                while i < n {
+                    match state.see_ge(moves[i], 0) {
+                        true => i += 1,
+                        false => {
+                            moves[i] = moves[n - 1];
+                            n -= 1;
+                        },
+                    }
+                }
+            },
+        }
+        moves[..n].sort_by_key(|&mv| -mvv_lva(mv));
+
+        for &mv in &moves[..n] {
+            let undo = state.push(mv);
+            self.nodes += 1;
+            let score = -self.quiescence(state, ply + 1, -beta, -alpha);
+            state.pop(mv, undo);
+            if self.stopped {
+                return DRAW;
+            }
+            if score > best {
+                best = score;
+                alpha = alpha.max(score);
+                if score >= beta {
+                    break;
+                }
+            }
+        }
+        best
+    }
+
+    fn has_non_pawn_material(state: &GameState) -> bool {
+        // Without pieces zugzwang is common, and passing is no guide to the best move
+        let color = state.turn();
+        state.occ(color)
+            & !state.bitboard[Pawn.as_color(color) as usize]
+            & !state.bitboard[King.as_color(color) as usize] != 0
+    }
+
+    fn on_prev_pv(&self) -> bool {
+        // Whether every move from the root so far followed the previous iteration's PV
+        self.path.len() < self.prev_pv.len()
+            && self.path.iter().zip(&self.prev_pv).all(|(mv, pv_move)| *mv == Some(*pv_move))
+    }
+
+    fn prev_move(&self) -> Option<Move> {
+        self.path.last().copied().flatten()
+    }
+
+    fn negamax(
+        &mut self,
+        state: &mut GameState,
+        mut depth: u64,
+        ply: usize,
+        mut alpha: i32,
+        beta: i32,
+        pv: &mut Vec<Move>
+    ) -> i32 {
+        pv.clear();
+        self.poll();
+        if self.stopped {
+            return DRAW;
+        }
+        if ply > 0 && (self.is_repetition(state)
+            || state.is_50_move_rule()
+            || state.is_insufficient_material()) {
+            return DRAW;
+        }
+
+        let in_check = state.in_check(state.turn());
+        if in_check && self.config.check_extensions {
+            depth += 1;
+        }
+        if depth == 0 || ply >= MAX_PLY - 1 {
+            return self.quiescence(state, ply, alpha, beta);
+        }
+
+        // PV nodes search on rather than cut off, so that the PV reaches past them
+        let is_pv = beta - alpha > 1;
+        let entry = self.tt.probe(state.zobrist_hash, ply);
+        if let Some(entry) = entry && ply > 0 && !is_pv && entry.depth as u64 >= depth {
+            let cutoff = match entry.bound {
+                Bound::Exact => true,
+                Bound::Lower => entry.score >= beta,
+                Bound::Upper => entry.score <= alpha,
+            };
+            if cutoff {
+                return entry.score;
+            }
+        }
+
+        // Selectivity only where a null window shows no exact score is wanted
+        let mate_bound = MATE - MAX_PLY as i32;
+        let static_eval = match in_check {
+            true => -INFINITY,
+            false => evaluate(state),
+        };
+        let mut child_pv = Vec::new();
+        if !is_pv && !in_check && beta.abs() < mate_bound {
+            // Far enough above beta that no quiet continuation will bring it back
+            if self.config.reverse_futility && depth <= REVERSE_FUTILITY_MAX_DEPTH
+                && static_eval - REVERSE_FUTILITY_MARGIN * depth as i32 >= beta {
+                return static_eval;
+            }
+            // Far enough below alpha that only captures are worth a look
+            if self.config.razoring && depth < RAZOR_MARGINS.len() as u64
+                && static_eval + RAZOR_MARGINS[depth as usize] <= alpha {
+                let score = self.quiescence(state, ply, alpha, beta);
+                if score <= alpha {
+                    return score;
+                }
+            }
+            // If passing still holds beta, a real move would too
+            if self.config.null_move && depth >= NULL_MOVE_MIN_DEPTH && static_eval >= beta
+                && self.path.last() != Some(&None)
+                && Searcher::has_non_pawn_material(state) {
+                let reduction = 2 + depth / 6;
+                self.hashes.push(state.zobrist_hash);
+                self.path.push(None);
+                let undo = state.push_null();
+                self.nodes += 1;
+                let score = -self.negamax(state, depth.saturating_sub(1 + reduction), ply + 1,
+                    -beta, -beta + 1, &mut child_pv);
+                state.pop_null(undo);
+                self.path.pop();
+                self.hashes.pop();
+                if self.stopped {
+                    return DRAW;
+                }
+                if score >= beta {
+                    // Mates found after passing are not proven
+                    return score.min(mate_bound - 1);
+                }
+            }
+        }
+
+        let hash_move = match entry.map_or(0, |e| e.best_move) {
+            0 if self.on_prev_pv() => self.prev_pv.get(ply).map_or(0, |mv| mv.compact()),
+            0 => 0,
+            compact => compact,
+        };
+        let mut picker = MovePicker::new(
+            state,
+            hash_move,
+            self.move_history.killers(ply),
+            self.move_history.countermove(self.prev_move()),
+        );
+        // Near the leaves quiet moves cannot raise a hopeless score to alpha
+        let futility_value = match FUTILITY_MARGINS.get(depth as usize) {
+            Some(margin) => static_eval + margin,
+            None => INFINITY,
+        };
+        let futile = self.config.futility && !is_pv && !in_check
+            && alpha.abs() < mate_bound && futility_value <= alpha;
+
+        let alpha_orig = alpha;
+        let mut best = -INFINITY;
+        let mut best_move = None;
+        let mut legal_moves = 0;
+        let mut quiets = [Move(0); MAX_LEGAL_MOVES];
+        let mut n_quiets = 0;
+        while let Some(mv) = picker.next(state, &self.move_history) {
+            if ply == 0 && self.excluded.contains(&mv) {
+                continue;
+            }
+            legal_moves += 1;
+            let is_quiet = !mv.is_capture() && !mv.is_promotion();
+            let gives_check = is_quiet && state.gives_check(mv);
+            let searched = legal_moves - 1;
+            if futile && searched > 0 && is_quiet && !gives_check {
+                best = best.max(futility_value);
+                continue;
+            }
+
+            self.hashes.push(state.zobrist_hash);
+            self.path.push(Some(mv));
+            let undo = state.push(mv);
+            self.nodes += 1;
+            // Late quiet moves are searched shallower with a null window first,
+            // and again in full only if they beat alpha after all
+            let reduction = match self.config.late_move_reductions && depth >= LMR_MIN_DEPTH
+                && searched >= LMR_MIN_MOVES && is_quiet && !in_check && !gives_check {
+                true => (1 + (!is_pv && searched >= 2 * LMR_MIN_MOVES) as u64).min(depth - 2),
+                false => 0,
+            };
+            let mut score = alpha + 1;
+            if reduction > 0 {
+                score = -self.negamax(state, depth - 1 - reduction, ply + 1, -alpha - 1, -alpha, &mut child_pv);
+            }
+            if score > alpha {
+                score = -self.negamax(state, depth - 1, ply + 1, -beta, -alpha, &mut child_pv);
+            }
+            state.pop(mv, undo);
+            self.path.pop();
+            self.hashes.pop();
+            if self.stopped {
+                return DRAW;
+            }
+            if score > best {
+                best = score;
+                if score > alpha {
+                    alpha = score;
+                    best_move = Some(mv);
+                    pv.clear();
+                    pv.push(mv);
+                    pv.extend_from_slice(&child_pv);
+                }
+                if score >= beta {
+                    if is_quiet {
+                        self.move_history.update_quiet(mv, self.prev_move(), ply, depth, &quiets[..n_quiets]);
+                    }
+                    break;
+                }
+            }
+            if is_quiet {
+                quiets[n_quiets] = mv;
+                n_quiets += 1;
+            }
+        }
+        if legal_moves == 0 {
+            return match in_check {
+                true => -MATE + ply as i32,
+                false => DRAW,
+            };
+        }
+        let bound = match (best <= alpha_orig, best >= beta) {
+            (true, _) => Bound::Upper,
+            (_, true) => Bound::Lower,
+            _ => Bound::Exact,
+        };
+        // A root searched without some of its moves has no true score
+        if ply > 0 || self.excluded.is_empty() {
+            self.tt.store(state.zobrist_hash, ply, depth as u8, bound, best, best_move);
+        }
+        best
+    }
+
+    fn begin(&mut self, history: &[u64], limits: SearchLimits) -> u64 {
+        // Resets the state of the previous search and returns the depth to search to
+        let max_depth = limits.depth.unwrap_or(MAX_PLY as u64 - 1).clamp(1, MAX_PLY as u64 - 1);
+        self.time_manager = limits.clock.as_ref().map(TimeManager::new);
+        self.limits = limits;
+        self.start = self.clock.now();
+        self.nodes = 0;
+        self.stopped = false;
+        self.hashes = history.to_vec();
+        self.path.clear();
+        self.prev_pv.clear();
+        self.excluded.clear();
+        if !self.helper {
+            self.thread_nodes = Arc::new((0..self.threads).map(|_| AtomicU64::new(0)).collect());
+            self.tt.new_search();
+        }
+        self.move_history.new_search();
+        max_depth
+    }
+
+    pub fn search<F>(
+        &mut self,
+        state: &GameState,
+        history: &[u64],
+        limits: SearchLimits,
+        report: F
+    ) -> SearchInfo
+    where
+        F: FnMut(&SearchInfo),
+    {
+        // history holds the Zobrist hashes of the positions before this one, oldest first
+        let max_depth = self.begin(history, limits);
+        if self.threads == 1 {
+            return self.iterate(state, max_depth, report);
+        }
+        // Helpers run until the main thread is done with its own iterations
+        let helpers_stop = Arc::new(AtomicBool::new(false));
+        let helpers: Vec<Searcher> = (1..self.threads).map(|i| self.helper(i, helpers_stop.clone())).collect();
+        let mut info = thread::scope(|scope| {
+            for mut helper in helpers {
+                let state = state.clone();
+                // Every other helper starts a ply deeper, so the threads spread over two depths
+                let start_depth = 1 + helper.thread_index as u64 % 2;
+                scope.spawn(move || helper.help(&state, history, start_depth, max_depth));
+            }
+            let info = self.iterate(state, max_depth, report);
+            helpers_stop.store(true, Ordering::Relaxed);
+            info
+        });
+        info.thread_nodes = self.node_counts();
+        info.nodes = info.thread_nodes.iter().sum();
+        info
+    }
+
+    fn help(&mut self, state: &GameState, history: &[u64], start_depth: u64, max_depth: u64) {
+        self.begin(history, SearchLimits::depth(max_depth));
+        let mut state = state.clone();
+        for depth in start_depth.min(max_depth)..=max_depth {
+            let mut pv = Vec::new();
+            self.negamax(&mut state, depth, 0, -INFINITY, INFINITY, &mut pv);
+            self.publish_nodes();
+            if self.stopped {
+                break;
+            }
+            self.prev_pv = pv;
+        }
+    }
+
+    fn iterate<F>(&mut self, state: &GameState, max_depth: u64, mut report: F) -> SearchInfo
+    where
+        F: FnMut(&SearchInfo),
+    {
+        let mut state = state.clone();
+        let mut moves = [Move(0); MAX_LEGAL_MOVES];
+        let only_move = state.generate_legal_moves(&mut moves) == 1;
+        let mut info = SearchInfo {
+            depth: 0,
+            score: evaluate(&state),
+            nodes: 0,
+            thread_nodes: Vec::new(),
+            elapsed: Duration::ZERO,
+            pv: Vec::new(),
+            hashfull: 0,
+        };
+        for depth in 1..=max_depth {
+            let mut pv = Vec::new();
+            let score = self.negamax(&mut state, depth, 0, -INFINITY, INFINITY, &mut pv);
+            if self.stopped {
+                break;
+            }
+            self.publish_nodes();
+            let thread_nodes = self.node_counts();
+            info = SearchInfo {
+                depth,
+                score,
+                nodes: thread_nodes.iter().sum(),
+                thread_nodes,
+                elapsed: self.elapsed(),
+                pv: pv.clone(),
+                hashfull: self.tt.hashfull(),
+            };
+            report(&info);
+            // No legal moves, a mate that deeper iterations cannot improve on, or out of time
+            if pv.is_empty() || MATE - score.abs() <= depth as i32 || self.out_of_limits() {
+                break;
+            }
+            if let Some(tm) = &mut self.time_manager {
+                tm.iteration_done(pv.first().copied(), score);
+                // Nothing to think about with a single legal move
+                if only_move || tm.should_stop(info.elapsed) {
+                    break;
+                }
+            }
+            self.prev_pv = pv;
+        }
+        info
+    }
+}
+
+
+pub fn best_move(state: &GameState, depth: u64) -> Option<Move> {
+    Searcher::new().search(state, &[], SearchLimits::depth(depth), |_| ()).best_move()
+}
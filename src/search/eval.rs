@@ -0,0 +1,107 @@
+// Material and piece-square tables, after Michniewski's simplified evaluation
+// https://www.chessprogramming.org/Simplified_Evaluation_Function
+
+use crate::{
+    board::{
+        color::Color::*,
+        piece::*,
+        square::*,
+    },
+    game::position::*,
+};
+
+
+// Indexed by GenericPiece
+pub const PIECE_VALUES: [i32; 6] = [500, 320, 330, 900, 0, 100];
+
+// Drawn with rank 8 at the top, as seen by white
+#[rustfmt::skip]
+const PIECE_SQUARE: [[i32; 64]; 6] = [
+    [   // Rook
+         0,  0,  0,  0,  0,  0,  0,  0,
+         5, 10, 10, 10, 10, 10, 10,  5,
+        -5,  0,  0,  0,  0,  0,  0, -5,
+        -5,  0,  0,  0,  0,  0,  0, -5,
+        -5,  0,  0,  0,  0,  0,  0, -5,
+        -5,  0,  0,  0,  0,  0,  0, -5,
+        -5,  0,  0,  0,  0,  0,  0, -5,
+         0,  0,  0,  5,  5,  0,  0,  0,
+    ],
+    [   // Knight
+        -50,-40,-30,-30,-30,-30,-40,-50,
+        -40,-20,  0,  0,  0,  0,-20,-40,
+        -30,  0, 10, 15, 15, 10,  0,-30,
+        -30,  5, 15, 20, 20, 15,  5,-30,
+        -30,  0, 15, 20, 20, 15,  0,-30,
+        -30,  5, 10, 15, 15, 10,  5,-30,
+        -40,-20,  0,  5,  5,  0,-20,-40,
+        -50,-40,-30,-30,-30,-30,-40,-50,
+    ],
+    [   // Bishop
+        -20,-10,-10,-10,-10,-10,-10,-20,
+        -10,  0,  0,  0,  0,  0,  0,-10,
+        -10,  0,  5, 10, 10,  5,  0,-10,
+        -10,  5,  5, 10, 10,  5,  5,-10,
+        -10,  0, 10, 10, 10, 10,  0,-10,
+        -10, 10, 10, 10, 10, 10, 10,-10,
+        -10,  5,  0,  0,  0,  0,  5,-10,
+        -20,-10,-10,-10,-10,-10,-10,-20,
+    ],
+    [   // Queen
+        -20,-10,-10, -5, -5,-10,-10,-20,
+        -10,  0,  0,  0,  0,  0,  0,-10,
+        -10,  0,  5,  5,  5,  5,  0,-10,
+         -5,  0,  5,  5,  5,  5,  0, -5,
+          0,  0,  5,  5,  5,  5,  0, -5,
+        -10,  5,  5,  5,  5,  5,  0,-10,
+        -10,  0,  5,  0,  0,  0,  0,-10,
+        -20,-10,-10, -5, -5,-10,-10,-20,
+    ],
+    [   // King, middlegame
+        -30,-40,-40,-50,-50,-40,-40,-30,
+        -30,-40,-40,-50,-50,-40,-40,-30,
+        -30,-40,-40,-50,-50,-40,-40,-30,
+        -30,-40,-40,-50,-50,-40,-40,-30,
+        -20,-30,-30,-40,-40,-30,-30,-20,
+        -10,-20,-20,-20,-20,-20,-20,-10,
+         20, 20,  0,  0,  0,  0, 20, 20,
+         20, 30, 10,  0,  0, 10, 30, 20,
+    ],
+    [   // Pawn
+         0,  0,  0,  0,  0,  0,  0,  0,
+        50, 50, 50, 50, 50, 50, 50, 50,
+        10, 10, 20, 30, 30, 20, 10, 10,
+         5,  5, 10, 25, 25, 10,  5,  5,
+         0,  0,  0, 20, 20,  0,  0,  0,
+         5, -5,-10,  0,  0,-10, -5,  5,
+         5, 10, 10,-20,-20, 10, 10,  5,
+         0,  0,  0,  0,  0,  0,  0,  0,
+    ],
+];
+
+
+pub const fn piece_square(piece: Piece, square: Square) -> i32 {
+    // Value of the piece on the square for its own side
+    let i = match piece.color() {
+        White => square as usize ^ 56,
+        Black => square as usize,
+    };
+    PIECE_VALUES[piece.as_generic() as usize] + PIECE_SQUARE[piece.as_generic() as usize][i]
+}
+
+pub fn evaluate(state: &GameState) -> i32 {
+    // Centipawns for the side to move
+    let mut score = 0;
+    for piece in Piece::ALL {
+        let mut mask = state.bitboard[piece as usize];
+        while mask != 0 {
+            let square = Square::ALL[mask.trailing_zeros() as usize];
+            score += match piece.color() == state.turn() {
+                true => piece_square(piece, square),
+                false => -piece_square(piece, square),
+            };
+            mask ^= square.mask();
+        }
+    }
+    score
+}
@@ -0,0 +1,164 @@
+// Proves or refutes a forced mate in a given number of moves, for problem solving
+// https://www.chessprogramming.org/Mate_Search
+
+use {
+    std::collections::HashMap,
+    crate::game::{
+        board_move::*,
+        move_gen::*,
+        position::*,
+    },
+};
+
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MateConfig {
+    pub checks_only: bool,  // The attacker only gives check
+    pub cooks: bool,  // Try every first move rather than stop at the first that mates
+}
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Solution {
+    // An attacking move and every legal defence to it; none when it mates
+    pub mv: Move,
+    pub defences: Vec<Defence>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Defence {
+    pub mv: Move,
+    pub reply: Solution,
+}
+
+impl Solution {
+
+    pub fn moves_to_mate(&self) -> u32 {
+        // Against the longest defence
+        1 + self.defences.iter().map(|d| d.reply.moves_to_mate()).max().unwrap_or(0)
+    }
+
+    pub fn variations(&self) -> Vec<Vec<Move>> {
+        // Every line from this move to mate
+        if self.defences.is_empty() {
+            return vec![vec![self.mv]];
+        }
+        let mut lines = Vec::new();
+        for defence in &self.defences {
+            for line in defence.reply.variations() {
+                let mut full = vec![self.mv, defence.mv];
+                full.extend(line);
+                lines.push(full);
+            }
+        }
+        lines
+    }
+}
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MateReport {
+    pub solution: Option<Solution>,
+    // Other first moves that also mate in time, found only when looking for cooks
+    pub cooks: Vec<Solution>,
+    pub nodes: u64,
+}
+
+
+struct MateFinder {
+    config: MateConfig,
+    nodes: u64,
+    // Positions with the attacker to move: the fewest moves a mate is proven in,
+    // and the most moves it is known to fail in
+    proven: HashMap<u64, (u32, Solution)>,
+    refuted: HashMap<u64, u32>,
+}
+
+impl MateFinder {
+
+    fn new(config: MateConfig) -> MateFinder {
+        MateFinder { config, nodes: 0, proven: HashMap::new(), refuted: HashMap::new() }
+    }
+
+    fn attacks(&self, state: &mut GameState) -> Vec<Move> {
+        // Checks first, since they leave the defence the fewest replies
+        let mut moves = [Move(0); MAX_LEGAL_MOVES];
+        let n = state.generate_legal_moves(&mut moves);
+        let (mut checks, quiet): (Vec<Move>, Vec<Move>) = moves[..n].iter()
+            .partition(|&&mv| state.gives_check(mv));
+        if !self.config.checks_only {
+            checks.extend(quiet);
+        }
+        checks
+    }
+
+    fn mates_with(&mut self, state: &mut GameState, mv: Move, moves: u32) -> Option<Solution> {
+        // Whether the move forces mate within the moves, this one included
+        let undo = state.push(mv);
+        self.nodes += 1;
+        let defences = self.defend(state, moves);
+        state.pop(mv, undo);
+        defences.map(|defences| Solution { mv, defences })
+    }
+
+    fn defend(&mut self, state: &mut GameState, moves: u32) -> Option<Vec<Defence>> {
+        // Every defence with a mating reply, or None if one holds out
+        let mut legal = [Move(0); MAX_LEGAL_MOVES];
+        let n = state.generate_legal_moves(&mut legal);
+        if n == 0 {
+            return match state.in_check(state.turn()) {
+                true => Some(Vec::new()),
+                false => None,  // Stalemate
+            };
+        }
+        if moves == 1 {
+            return None;
+        }
+        let mut defences = Vec::with_capacity(n);
+        for &mv in &legal[..n] {
+            let undo = state.push(mv);
+            self.nodes += 1;
+            let reply = self.attack(state, moves - 1);
+            state.pop(mv, undo);
+            defences.push(Defence { mv, reply: reply? });
+        }
+        Some(defences)
+    }
+
+    fn attack(&mut self, state: &mut GameState, moves: u32) -> Option<Solution> {
+        // A mate within the moves, not necessarily the quickest
+        let key = state.zobrist_hash;
+        if let Some((proven, solution)) = self.proven.get(&key) && *proven <= moves {
+            return Some(solution.clone());
+        }
+        if self.refuted.get(&key).is_some_and(|&refuted| refuted >= moves) {
+            return None;
+        }
+        for mv in self.attacks(state) {
+            if let Some(solution) = self.mates_with(state, mv, moves) {
+                self.proven.insert(key, (solution.moves_to_mate(), solution.clone()));
+                return Some(solution);
+            }
+        }
+        self.refuted.insert(key, moves);
+        None
+    }
+}
+
+
+pub fn find_mate(state: &GameState, moves: u32, config: MateConfig) -> MateReport {
+    // Mate in at most the given moves for the side to move. Only the key is the
+    // quickest; later moves of the solution mate in time, but maybe not at once.
+    let mut finder = MateFinder::new(config);
+    let mut state = state.clone();
+    let attacks = finder.attacks(&mut state);
+    let solution = (1..=moves).find_map(|depth| attacks.iter()
+        .find_map(|&mv| finder.mates_with(&mut state, mv, depth)));
+    let mut cooks = Vec::new();
+    if let Some(key) = &solution && config.cooks {
+        for &mv in attacks.iter().filter(|&&mv| mv != key.mv) {
+            cooks.extend(finder.mates_with(&mut state, mv, moves));
+        }
+    }
+    MateReport { solution, cooks, nodes: finder.nodes }
+}
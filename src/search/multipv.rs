@@ -0,0 +1,109 @@
+// Several best lines at once, each root move searched with the better ones left out
+// https://www.chessprogramming.org/Principal_Variation#Multiple_PVs
+
+use {
+    crate::game::{
+        board_move::*,
+        move_gen::*,
+        position::*,
+    },
+    super::*,
+};
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PvLine {
+    pub depth: u64,
+    pub score: i32,
+    pub pv: Vec<Move>,
+    pub pacn: Vec<String>,
+    pub san: Vec<String>,
+}
+
+impl PvLine {
+
+    fn new(state: &GameState, depth: u64, score: i32, pv: Vec<Move>) -> PvLine {
+        let mut state = state.clone();
+        let mut san = Vec::new();
+        for &mv in &pv {
+            san.push(mv.san(&state));
+            state.push(mv);
+        }
+        let pacn = pv.iter().map(|mv| mv.pacn()).collect();
+        PvLine { depth, score, pv, pacn, san }
+    }
+
+    pub const fn score(&self) -> Score {
+        Score::from_value(self.score)
+    }
+
+    pub fn best_move(&self) -> Option<Move> {
+        self.pv.first().copied()
+    }
+}
+
+
+impl Searcher {
+
+    pub fn search_multipv<F>(
+        &mut self,
+        state: &GameState,
+        history: &[u64],
+        limits: SearchLimits,
+        lines: usize,
+        mut report: F
+    ) -> Vec<PvLine>
+    where
+        F: FnMut(&[PvLine]),
+    {
+        // The best lines of the last completed iteration, best first. There are
+        // fewer than asked for only when there are fewer legal moves.
+        let max_depth = self.begin(history, limits);
+        let mut state = state.clone();
+        let mut moves = [Move(0); MAX_LEGAL_MOVES];
+        let lines = lines.min(state.generate_legal_moves(&mut moves));
+
+        let mut result: Vec<PvLine> = Vec::new();
+        for depth in 1..=max_depth {
+            let mut found: Vec<PvLine> = Vec::new();
+            self.excluded.clear();
+            for i in 0..lines {
+                // Each line is seeded with its own PV from the iteration before
+                self.prev_pv = result.get(i).map_or_else(Vec::new, |line| line.pv.clone());
+                let mut pv = Vec::new();
+                let score = self.negamax(&mut state, depth, 0, -INFINITY, INFINITY, &mut pv);
+                if self.stopped || pv.is_empty() {
+                    break;
+                }
+                self.excluded.push(pv[0]);
+                found.push(PvLine::new(&state, depth, score, pv));
+            }
+            if self.stopped || found.len() < lines {
+                break;
+            }
+            found.sort_by_key(|line| -line.score);
+            result = found;
+            report(&result);
+
+            let elapsed = self.elapsed();
+            // Every line a mate that deeper iterations cannot improve on, or out of time
+            if result.iter().all(|line| MATE - line.score.abs() <= depth as i32) || self.out_of_limits() {
+                break;
+            }
+            if let Some(tm) = &mut self.time_manager {
+                tm.iteration_done(result[0].best_move(), result[0].score);
+                if tm.should_stop(elapsed) {
+                    break;
+                }
+            }
+        }
+        self.excluded.clear();
+        result
+    }
+}
+
+
+pub fn analyse(state: &GameState, lines: usize, depth: u64) -> Vec<PvLine> {
+    // The best lines to the depth, best first
+    Searcher::new().search_multipv(state, &[], SearchLimits::depth(depth), lines, |_| ())
+}
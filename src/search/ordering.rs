@@ -0,0 +1,296 @@
+// Staged move picker with killer, countermove and history heuristics
+// https://www.chessprogramming.org/Move_Ordering
+
+use {
+    crate::{
+        board::{
+            piece::*,
+            square::*,
+        },
+        game::{
+            board_move::*,
+            move_gen::*,
+            position::*,
+        },
+    },
+    super::{
+        *,
+        eval::*,
+    },
+};
+
+
+// Bound on history scores; updates shrink towards it rather than overflow
+const MAX_HISTORY: i32 = 16_384;
+
+
+pub fn mvv_lva(mv: Move) -> i32 {
+    // Most valuable victim first, then least valuable attacker, with promotions counted as gains
+    let victim = match mv.captured_piece() {
+        Some(piece) => PIECE_VALUES[piece.as_generic() as usize],
+        None => 0,
+    };
+    let promotion = match mv.promotion() {
+        Some(p) => PIECE_VALUES[p.as_generic_piece() as usize],
+        None => 0,
+    };
+    10 * (victim + promotion) - PIECE_VALUES[mv.origin_piece().as_generic() as usize] / 100
+}
+
+fn decode(state: &GameState, compact: u16) -> Option<Move> {
+    // Inverse of Move::compact, for legal moves only
+    let origin = Square::ALL[(compact & 0x3F) as usize];
+    let destination = Square::ALL[(compact >> 6 & 0x3F) as usize];
+    let promotion = match compact >> 12 {
+        0 => None,
+        p => Some(*Promotion::ALL.get(p as usize - 1)?),
+    };
+    state.try_move(origin, destination, promotion).ok()
+}
+
+
+pub struct MoveHistory {
+    killers: [[u16; 2]; MAX_PLY],  // Quiet moves that caused a cutoff at each ply
+    countermoves: [[u16; 64]; 12],  // Refutations of the previous move, by its piece and destination
+    butterfly: [[i32; 64]; 12],  // Quiet move success, by piece and destination
+}
+
+impl Default for MoveHistory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MoveHistory {
+
+    pub fn new() -> MoveHistory {
+        MoveHistory {
+            killers: [[0; 2]; MAX_PLY],
+            countermoves: [[0; 64]; 12],
+            butterfly: [[0; 64]; 12],
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = MoveHistory::new();
+    }
+
+    pub fn new_search(&mut self) {
+        // Killers belong to the old root; history is kept but fades
+        self.killers = [[0; 2]; MAX_PLY];
+        for scores in &mut self.butterfly {
+            scores.iter_mut().for_each(|s| *s /= 2);
+        }
+    }
+
+    pub const fn killers(&self, ply: usize) -> [u16; 2] {
+        self.killers[ply]
+    }
+
+    pub fn countermove(&self, prev: Option<Move>) -> u16 {
+        prev.map_or(0, |mv| self.countermoves[mv.destination_piece() as usize][mv.destination_square() as usize])
+    }
+
+    pub const fn history(&self, mv: Move) -> i32 {
+        self.butterfly[mv.origin_piece() as usize][mv.destination_square() as usize]
+    }
+
+    fn add_history(&mut self, mv: Move, bonus: i32) {
+        let entry = &mut self.butterfly[mv.origin_piece() as usize][mv.destination_square() as usize];
+        *entry += bonus - *entry * bonus.abs() / MAX_HISTORY;
+    }
+
+    pub fn update_quiet(&mut self, mv: Move, prev: Option<Move>, ply: usize, depth: u64, tried: &[Move]) {
+        // A quiet move caused a cutoff after the other quiets tried had failed
+        let compact = mv.compact();
+        if self.killers[ply][0] != compact {
+            self.killers[ply] = [compact, self.killers[ply][0]];
+        }
+        if let Some(prev) = prev {
+            self.countermoves[prev.destination_piece() as usize][prev.destination_square() as usize] = compact;
+        }
+        let bonus = (depth * depth).min(MAX_HISTORY as u64) as i32;
+        self.add_history(mv, bonus);
+        for &other in tried {
+            self.add_history(other, -bonus);
+        }
+    }
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stage {
+    HashMove,
+    Generate,
+    GoodCaptures,
+    GenerateQuiets,
+    FirstKiller,
+    SecondKiller,
+    Countermove,
+    ScoreQuiets,
+    Quiets,
+    BadCaptures,
+    Done,
+}
+
+pub struct MovePicker {
+    stage: Stage,
+    hash_move: Option<Move>,
+    killers: [u16; 2],
+    countermove: u16,
+    moves: [Move; MAX_LEGAL_MOVES],
+    scores: [i32; MAX_LEGAL_MOVES],
+    len: usize,
+    in_check: bool,
+    captures_end: usize,  // Captures and promotions come before the quiets
+    bad_end: usize,  // Losing captures are set aside at the front as they are found
+    cur: usize,
+}
+
+impl MovePicker {
+
+    pub fn new(state: &GameState, hash_move: u16, killers: [u16; 2], countermove: u16) -> MovePicker {
+        MovePicker {
+            stage: Stage::HashMove,
+            hash_move: match hash_move {
+                0 => None,
+                compact => decode(state, compact),
+            },
+            killers,
+            countermove,
+            moves: [Move(0); MAX_LEGAL_MOVES],
+            scores: [0; MAX_LEGAL_MOVES],
+            len: 0,
+            in_check: false,
+            captures_end: 0,
+            bad_end: 0,
+            cur: 0,
+        }
+    }
+
+    fn is_hash_move(&self, mv: Move) -> bool {
+        self.hash_move.is_some_and(|hash| hash.compact() == mv.compact())
+    }
+
+    fn generate(&mut self, state: &mut GameState) {
+        // Out of check only captures and promotions for now; in check every evasion at once
+        self.in_check = state.in_check(state.turn());
+        self.len = match self.in_check {
+            true => state.generate_evasions(&mut self.moves),
+            false => state.generate_captures(&mut self.moves),
+        };
+        for i in 0..self.len {
+            let mv = self.moves[i];
+            if mv.is_capture() || mv.is_promotion() {
+                self.moves.swap(i, self.captures_end);
+                self.scores[self.captures_end] = mvv_lva(mv);
+                self.captures_end += 1;
+            }
+        }
+    }
+
+    fn generate_quiets(&mut self, state: &mut GameState) {
+        if self.in_check {
+            return;
+        }
+        let mut quiets = [Move(0); MAX_LEGAL_MOVES];
+        let n = state.generate_quiets(&mut quiets);
+        self.moves[self.len..self.len + n].copy_from_slice(&quiets[..n]);
+        self.len += n;
+    }
+
+    fn pick_best(&mut self, end: usize) -> Option<Move> {
+        // Selection sort, one move at a time
+        let best = (self.cur..end).max_by_key(|&i| self.scores[i])?;
+        self.moves.swap(self.cur, best);
+        self.scores.swap(self.cur, best);
+        self.cur += 1;
+        Some(self.moves[self.cur - 1])
+    }
+
+    fn find_quiet(&self, compact: u16) -> Option<Move> {
+        if compact == 0 {
+            return None;
+        }
+        self.moves[self.captures_end..self.len].iter().copied()
+            .find(|&mv| mv.compact() == compact && !self.is_hash_move(mv))
+    }
+
+    fn is_refutation(&self, mv: Move) -> bool {
+        // Already tried in an earlier stage
+        let compact = mv.compact();
+        self.is_hash_move(mv) || self.killers.contains(&compact) || self.countermove == compact
+    }
+
+    pub fn next(&mut self, state: &mut GameState, history: &MoveHistory) -> Option<Move> {
+        loop {
+            match self.stage {
+                Stage::HashMove => {
+                    self.stage = Stage::Generate;
+                    if self.hash_move.is_some() {
+                        return self.hash_move;
+                    }
+                },
+                Stage::Generate => {
+                    self.generate(state);
+                    self.stage = Stage::GoodCaptures;
+                },
+                Stage::GoodCaptures => match self.pick_best(self.captures_end) {
+                    Some(mv) if self.is_hash_move(mv) => (),
+                    Some(mv) if !state.see_ge(mv, 0) => {
+                        self.moves.swap(self.bad_end, self.cur - 1);
+                        self.bad_end += 1;
+                    },
+                    Some(mv) => return Some(mv),
+                    None => self.stage = Stage::GenerateQuiets,
+                },
+                Stage::GenerateQuiets => {
+                    self.generate_quiets(state);
+                    self.stage = Stage::FirstKiller;
+                },
+                Stage::FirstKiller => {
+                    self.stage = Stage::SecondKiller;
+                    if let Some(mv) = self.find_quiet(self.killers[0]) {
+                        return Some(mv);
+                    }
+                },
+                Stage::SecondKiller => {
+                    self.stage = Stage::Countermove;
+                    if let Some(mv) = self.find_quiet(self.killers[1]) {
+                        return Some(mv);
+                    }
+                },
+                Stage::Countermove => {
+                    self.stage = Stage::ScoreQuiets;
+                    if !self.killers.contains(&self.countermove)
+                        && let Some(mv) = self.find_quiet(self.countermove) {
+                        return Some(mv);
+                    }
+                },
+                Stage::ScoreQuiets => {
+                    for i in self.captures_end..self.len {
+                        self.scores[i] = history.history(self.moves[i]);
+                    }
+                    self.cur = self.captures_end;
+                    self.stage = Stage::Quiets;
+                },
+                Stage::Quiets => match self.pick_best(self.len) {
+                    Some(mv) if self.is_refutation(mv) => (),
+                    Some(mv) => return Some(mv),
+                    None => {
+                        self.cur = 0;
+                        self.stage = Stage::BadCaptures;
+                    },
+                },
+                Stage::BadCaptures => match self.cur < self.bad_end {
+                    true => {
+                        self.cur += 1;
+                        return Some(self.moves[self.cur - 1]);
+                    },
+                    false => self.stage = Stage::Done,
+                },
+                Stage::Done => return None,
+            }
+        }
+    }
+}
@@ -0,0 +1,165 @@
+// Time allocation for games played on a clock
+// https://www.chessprogramming.org/Time_Management
+
+use {
+    std::{
+        sync::atomic::{AtomicU64, Ordering},
+        time::{Duration, Instant},
+    },
+    crate::game::board_move::*,
+};
+
+
+// Moves the remaining time is spread over when the control does not say
+const DEFAULT_MOVES_TO_GO: u64 = 25;
+// Share of the increment spent on top of the base allocation, in percent
+const INCREMENT_SHARE: u32 = 75;
+// Shares of the time left after overhead that the deadlines may not exceed, in percent
+const MAX_SOFT_SHARE: u32 = 50;
+const MAX_HARD_SHARE: u32 = 80;
+// The hard deadline is this many soft deadlines, within MAX_HARD_SHARE
+const HARD_PER_SOFT: u32 = 4;
+// Score drops between iterations, in centipawns, that earn more time
+const SMALL_DROP: i32 = 25;
+const LARGE_DROP: i32 = 75;
+
+pub const DEFAULT_MOVE_OVERHEAD: Duration = Duration::from_millis(30);
+
+
+pub trait Clock: Send + Sync {
+    // Time since some fixed point; only differences are meaningful
+    fn now(&self) -> Duration;
+}
+
+pub struct SystemClock {
+    origin: Instant,
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SystemClock {
+
+    pub fn new() -> SystemClock {
+        SystemClock { origin: Instant::now() }
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.origin.elapsed()
+    }
+}
+
+pub struct ManualClock {
+    // For tests: time moves only when advanced, and optionally on every reading
+    micros: AtomicU64,
+    step: Duration,
+}
+
+impl ManualClock {
+
+    pub const fn new() -> ManualClock {
+        ManualClock::ticking(Duration::ZERO)
+    }
+
+    pub const fn ticking(step: Duration) -> ManualClock {
+        ManualClock { micros: AtomicU64::new(0), step }
+    }
+
+    pub fn advance(&self, by: Duration) {
+        self.micros.fetch_add(by.as_micros() as u64, Ordering::Relaxed);
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        let micros = self.micros.fetch_add(self.step.as_micros() as u64, Ordering::Relaxed);
+        Duration::from_micros(micros)
+    }
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClockLimits {
+    // The clock of the side to move
+    pub remaining: Duration,
+    pub increment: Duration,
+    pub moves_to_go: Option<u64>,  // Until the next time control, None for sudden death
+    // Lost to communication and the GUI on every move
+    pub overhead: Duration,
+}
+
+impl ClockLimits {
+
+    pub const fn new(remaining: Duration, increment: Duration, moves_to_go: Option<u64>) -> ClockLimits {
+        ClockLimits { remaining, increment, moves_to_go, overhead: DEFAULT_MOVE_OVERHEAD }
+    }
+}
+
+
+#[derive(Debug, Clone)]
+pub struct TimeManager {
+    soft: Duration,  // No new iteration is started after this, scaled for instability
+    hard: Duration,  // The search stops here whatever it is doing
+    instability: f64,  // Recent changes of the best move, decaying each iteration
+    score_drop: i32,
+    prev_best: Option<Move>,
+    prev_score: Option<i32>,
+}
+
+impl TimeManager {
+
+    pub fn new(clock: &ClockLimits) -> TimeManager {
+        let available = clock.remaining.saturating_sub(clock.overhead);
+        let moves_to_go = clock.moves_to_go.unwrap_or(DEFAULT_MOVES_TO_GO).max(1) as u32;
+        let base = available / moves_to_go + clock.increment * INCREMENT_SHARE / 100;
+        let soft = base.min(available * MAX_SOFT_SHARE / 100);
+        let hard = (soft * HARD_PER_SOFT).min(available * MAX_HARD_SHARE / 100);
+        TimeManager {
+            soft,
+            hard,
+            instability: 0.0,
+            score_drop: 0,
+            prev_best: None,
+            prev_score: None,
+        }
+    }
+
+    pub const fn hard_deadline(&self) -> Duration {
+        self.hard
+    }
+
+    pub fn soft_deadline(&self) -> Duration {
+        // Longer while the best move keeps changing or the score is falling
+        let drop_scale = match self.score_drop {
+            d if d < SMALL_DROP => 1.0,
+            d if d < LARGE_DROP => 1.3,
+            _ => 1.6,
+        };
+        self.soft.mul_f64((1.0 + 0.5 * self.instability) * drop_scale).min(self.hard)
+    }
+
+    pub fn iteration_done(&mut self, best: Option<Move>, score: i32) {
+        self.instability *= 0.5;
+        if self.prev_best.is_some() && best != self.prev_best {
+            self.instability += 1.0;
+        }
+        self.score_drop = self.prev_score.map_or(0, |prev| prev - score);
+        self.prev_best = best;
+        self.prev_score = Some(score);
+    }
+
+    pub fn should_stop(&self, elapsed: Duration) -> bool {
+        elapsed >= self.soft_deadline()
+    }
+}
@@ -0,0 +1,216 @@
+// Transposition table for search, keyed on the Zobrist hash.
+// Shared between search threads without locks: each slot stores its key
+// XORed with its data, so an entry torn by a concurrent write fails to verify.
+// https://www.chessprogramming.org/Transposition_Table
+// https://www.chessprogramming.org/Shared_Hash_Table#Lock-less
+
+use {
+    std::{
+        mem::size_of,
+        sync::atomic::{AtomicU8, AtomicU64, Ordering},
+    },
+    crate::game::board_move::*,
+    super::*,
+};
+
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    Exact = 1,  // 0 marks an empty slot
+    Lower,  // Failed high: the score is at least this
+    Upper,  // Failed low: the score is at most this
+}
+
+impl Bound {
+    const ALL: [Bound; 3] = [Bound::Exact, Bound::Lower, Bound::Upper];
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TtEntry {
+    pub depth: u8,
+    pub bound: Bound,
+    pub score: i32,  // Relative to the probing ply
+    pub best_move: u16,  // Move::compact, 0 for none
+    pub age: u8,
+}
+
+
+// Layout of the data word
+const MOVE_OFFSET: u64 = 0;
+const SCORE_OFFSET: u64 = 16;
+const DEPTH_OFFSET: u64 = 32;
+const BOUND_OFFSET: u64 = 40;
+const AGE_OFFSET: u64 = 42;
+const AGE_BITS: u64 = 6;
+
+// Entries sampled by hashfull
+const HASHFULL_SAMPLE: usize = 1000;
+
+
+pub const fn score_to_tt(score: i32, ply: usize) -> i32 {
+    // Mate scores are stored as distance from this node rather than the root
+    match (score >= MATE - MAX_PLY as i32, score <= -MATE + MAX_PLY as i32) {
+        (true, _) => score + ply as i32,
+        (_, true) => score - ply as i32,
+        _ => score,
+    }
+}
+
+pub const fn score_from_tt(score: i32, ply: usize) -> i32 {
+    match (score >= MATE - MAX_PLY as i32, score <= -MATE + MAX_PLY as i32) {
+        (true, _) => score - ply as i32,
+        (_, true) => score + ply as i32,
+        _ => score,
+    }
+}
+
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Slot {
+    key: u64,  // Verification key, the full hash
+    data: u64,
+}
+
+impl Slot {
+
+    const fn bound_code(self) -> u64 {
+        self.data >> BOUND_OFFSET & 0b11
+    }
+
+    const fn depth(self) -> u8 {
+        (self.data >> DEPTH_OFFSET) as u8
+    }
+
+    const fn age(self) -> u8 {
+        (self.data >> AGE_OFFSET & ((1 << AGE_BITS) - 1)) as u8
+    }
+}
+
+#[derive(Debug, Default)]
+struct AtomicSlot {
+    checked_key: AtomicU64,  // key ^ data
+    data: AtomicU64,
+}
+
+impl AtomicSlot {
+
+    fn load(&self) -> Slot {
+        let checked_key = self.checked_key.load(Ordering::Relaxed);
+        let data = self.data.load(Ordering::Relaxed);
+        Slot { key: checked_key ^ data, data }
+    }
+
+    fn save(&self, slot: Slot) {
+        self.checked_key.store(slot.key ^ slot.data, Ordering::Relaxed);
+        self.data.store(slot.data, Ordering::Relaxed);
+    }
+}
+
+pub struct TranspositionTable {
+    slots: Vec<AtomicSlot>,
+    age: AtomicU8,
+}
+
+impl TranspositionTable {
+
+    pub const DEFAULT_MB: usize = 16;
+
+    pub fn new(megabytes: usize) -> TranspositionTable {
+        TranspositionTable::with_capacity(megabytes * 1024 * 1024 / size_of::<Slot>())
+    }
+
+    pub fn with_capacity(entries: usize) -> TranspositionTable {
+        // Rounded down to a power of two so the index is a mask
+        let entries = match entries {
+            0 => 1,
+            n => 1 << n.ilog2(),
+        };
+        TranspositionTable {
+            slots: (0..entries).map(|_| AtomicSlot::default()).collect(),
+            age: AtomicU8::new(0),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn megabytes(&self) -> usize {
+        self.slots.len() * size_of::<Slot>() / (1024 * 1024)
+    }
+
+    fn age(&self) -> u8 {
+        self.age.load(Ordering::Relaxed)
+    }
+
+    pub fn clear(&self) {
+        self.slots.iter().for_each(|slot| slot.save(Slot::default()));
+        self.age.store(0, Ordering::Relaxed);
+    }
+
+    pub fn new_search(&self) {
+        // Entries from earlier searches become the first to be replaced
+        self.age.store((self.age() + 1) % (1 << AGE_BITS), Ordering::Relaxed);
+    }
+
+    pub fn hashfull(&self) -> u32 {
+        // Permille of a sample of slots holding entries from the current search
+        let sample = &self.slots[..HASHFULL_SAMPLE.min(self.slots.len())];
+        let used = sample.iter().map(AtomicSlot::load)
+            .filter(|s| s.bound_code() != 0 && s.age() == self.age()).count();
+        (used * 1000 / sample.len()) as u32
+    }
+
+    const fn index(&self, key: u64) -> usize {
+        key as usize & (self.slots.len() - 1)
+    }
+
+    pub fn probe(&self, key: u64, ply: usize) -> Option<TtEntry> {
+        let slot = self.slots[self.index(key)].load();
+        if slot.key != key || slot.bound_code() == 0 {
+            return None;
+        }
+        Some(TtEntry {
+            depth: slot.depth(),
+            bound: Bound::ALL[slot.bound_code() as usize - 1],
+            score: score_from_tt((slot.data >> SCORE_OFFSET) as u16 as i16 as i32, ply),
+            best_move: (slot.data >> MOVE_OFFSET) as u16,
+            age: slot.age(),
+        })
+    }
+
+    pub fn store(
+        &self,
+        key: u64,
+        ply: usize,
+        depth: u8,
+        bound: Bound,
+        score: i32,
+        best_move: Option<Move>
+    ) {
+        let i = self.index(key);
+        let old = self.slots[i].load();
+        let age = self.age();
+        // Keep a deeper entry for another position from this search
+        if old.bound_code() != 0 && old.key != key && old.age() == age && old.depth() > depth {
+            return;
+        }
+        // Keep the old move when failing low gives no new one
+        let best_move = match (best_move, old.key == key) {
+            (Some(mv), _) => mv.compact(),
+            (None, true) => (old.data >> MOVE_OFFSET) as u16,
+            (None, false) => 0,
+        };
+        let score = score_to_tt(score, ply) as i16 as u16;
+        self.slots[i].save(Slot {
+            key,
+            data: (best_move as u64) << MOVE_OFFSET
+                | (score as u64) << SCORE_OFFSET
+                | (depth as u64) << DEPTH_OFFSET
+                | (bound as u64) << BOUND_OFFSET
+                | (age as u64) << AGE_OFFSET,
+        });
+    }
+}
@@ -0,0 +1,52 @@
+// Helpers and positions shared by the integration tests. Each test crate uses
+// only some of them.
+#![allow(dead_code)]
+
+use sublime::{
+    game::{
+        board_move::*,
+        move_gen::*,
+        position::*,
+    },
+    perft::bench::Generator,
+};
+
+
+pub const MATE_IN_ONE: &str = "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1";  // Ra8#
+pub const MATE_IN_TWO: &str = "7k/8/5K2/8/8/8/8/6R1 w - - 0 1";  // Kf7 Kh7 Rh1#
+pub const MATE_IN_THREE: &str = "r1b1kb1r/pppp1ppp/5q2/4n3/3KP3/2N3PN/PPP4P/R1BQ1B1R b kq - 0 1";  // Bc5+, all checks
+pub const MATED: &str = "R5k1/8/6K1/8/8/8/8/8 b - - 0 1";
+pub const WINS_QUEEN: &str = "4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1";  // Rxd5
+pub const ONLY_MOVE: &str = "k7/8/8/8/8/8/1r6/K7 w - - 0 1";  // Kxb2
+
+
+pub fn generated(state: &mut GameState, generator: Generator) -> Vec<u64> {
+    // The generator's moves, sorted so that generators can be compared
+    let mut moves = [Move(0); MAX_LEGAL_MOVES];
+    let n = generator(state, &mut moves);
+    let mut moves: Vec<u64> = moves[..n].iter().map(|mv| mv.0).collect();
+    moves.sort();
+    moves
+}
+
+pub fn legal_moves(state: &mut GameState) -> Vec<Move> {
+    let mut moves = [Move(0); MAX_LEGAL_MOVES];
+    let n = state.generate_legal_moves(&mut moves);
+    moves[..n].to_vec()
+}
+
+pub fn for_each_position<F>(state: &mut GameState, depth: u64, visit: &mut F)
+where
+    F: FnMut(&mut GameState),
+{
+    // Calls visit on the position and on every position up to depth plies from it
+    visit(state);
+    if depth == 0 {
+        return;
+    }
+    for mv in legal_moves(state) {
+        let undo = state.push(mv);
+        for_each_position(state, depth - 1, visit);
+        state.pop(mv, undo);
+    }
+}
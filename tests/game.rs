@@ -0,0 +1,95 @@
+use sublime::{
+    board::{
+        color::Color::*,
+        square::Square::*,
+    },
+    game::{
+        board_move::*,
+        history::*,
+    },
+    parse::pacn::*,
+};
+
+
+fn play(game: &mut Game, moves: &str) {
+    for s in moves.split_whitespace() {
+        let mv = Move::from_str(game.state(), s).unwrap();
+        game.push(mv).unwrap();
+    }
+}
+
+
+#[test]
+fn checkmate_ends_game() {
+    let mut game = Game::new();
+    play(&mut game, "f2f3 e7e5 g2g4 d8h4");
+    assert_eq!(game.outcome(), Some((GameResult::Win(Black), Termination::Checkmate)));
+    assert!(matches!(Move::from_str(game.state(), "e1f2"),
+        Err(PacnError::IllegalMove(IllegalMove::InCheck))));
+    let mv = Move::new(game.state(), E1, F2, None);
+    assert!(matches!(game.push(mv), Err(GameError::GameOver(..))));
+    assert_eq!(game.pop().map(|mv| mv.pacn()), Some(String::from("d8h4")));
+    assert!(!game.is_over());
+}
+
+#[test]
+fn stalemate() {
+    let mut game = Game::from_fen("7k/5Q2/6K1/8/8/8/8/8 w - - 0 1").unwrap();
+    play(&mut game, "f7e7");
+    assert!(!game.is_over());
+    let mut game = Game::from_fen("7k/8/5QK1/8/8/8/8/8 w - - 0 1").unwrap();
+    play(&mut game, "f6f7");
+    assert_eq!(game.outcome(), Some((GameResult::Draw, Termination::Stalemate)));
+}
+
+#[test]
+fn repetition() {
+    let mut game = Game::new();
+    let shuffle = "g1f3 g8f6 f3g1 f6g8";
+    play(&mut game, shuffle);
+    assert_eq!(game.repetitions(), 2);
+    assert_eq!(game.claimable_draw(), None);
+    play(&mut game, shuffle);
+    assert_eq!(game.claimable_draw(), Some(Termination::ThreefoldRepetition));
+    play(&mut game, shuffle);
+    play(&mut game, shuffle);
+    assert_eq!(game.outcome(), Some((GameResult::Draw, Termination::FivefoldRepetition)));
+
+    game.pop();
+    assert!(!game.is_over());
+    assert_eq!(game.claim_draw().unwrap(), Termination::ThreefoldRepetition);
+    assert!(game.is_over());
+
+    let mut game = Game::new();
+    play(&mut game, "g1f3 g8f6");
+    assert!(matches!(game.claim_draw(), Err(GameError::NoDrawToClaim)));
+}
+
+#[test]
+fn move_rules() {
+    let mut game = Game::from_fen("4k3/8/8/8/8/8/4P3/R3K3 w - - 98 80").unwrap();
+    play(&mut game, "a1a2");
+    assert_eq!(game.claimable_draw(), None);
+    play(&mut game, "e8d8");
+    assert_eq!(game.claimable_draw(), Some(Termination::FiftyMoveRule));
+
+    let mut game = Game::from_fen("4k3/8/8/8/8/8/4P3/R3K3 w - - 149 100").unwrap();
+    play(&mut game, "a1a2");
+    assert_eq!(game.outcome(), Some((GameResult::Draw, Termination::SeventyFiveMoveRule)));
+}
+
+#[test]
+fn insufficient_material() {
+    for (fen, dead) in [
+        ("4k3/8/8/8/8/8/8/4K3 w - - 0 1", true),
+        ("4k3/8/8/8/8/8/8/4KN2 w - - 0 1", true),
+        ("4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1", true),
+        ("4k1b1/8/8/8/8/8/8/2B1K3 w - - 0 1", false),
+        ("4k3/8/8/8/8/8/8/3NKN2 w - - 0 1", false),
+        ("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", false),
+    ] {
+        let game = Game::from_fen(fen).unwrap();
+        assert_eq!(game.state().is_insufficient_material(), dead, "{}", fen);
+        assert_eq!(game.is_over(), dead, "{}", fen);
+    }
+}
@@ -0,0 +1,64 @@
+use sublime::{
+    board::square::Square::*,
+    game::{
+        board_move::*,
+        move_gen::*,
+        position::*,
+    },
+    perft::{
+        FENS,
+        bench::*,
+    },
+};
+
+mod common;
+use common::*;
+
+
+fn check_agree(state: &mut GameState, depth: u64) {
+    for_each_position(state, depth, &mut |state| {
+        let legal = generated(state, GameState::generate_legal_moves);
+        assert_eq!(legal, generated(state, GameState::generate_legal_moves_by_make_unmake), "{}", state.fen());
+    });
+}
+
+
+#[test]
+fn generators_agree_on_the_perft_positions() {
+    for fen in FENS {
+        check_agree(&mut GameState::from_fen(fen).unwrap(), 2);
+    }
+    assert!(bench_generators(3).iter().all(|result| result.passed()));
+}
+
+#[test]
+fn pins_and_en_passant() {
+    for (fen, n) in [
+        // Taking en passant would leave both pawns off the rank between king and queen
+        ("8/8/8/8/k2Pp2Q/8/8/4K3 b - d3 0 1", 6),
+        ("8/8/8/8/k2Pp3/8/8/4K3 b - d3 0 1", 7),
+        // A pinned pawn may still take en passant along its pin
+        ("8/7k/8/8/3Pp3/8/2B5/4K3 b - d3 0 1", 6),
+        // The checking pawn is removed en passant
+        ("8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1", 9),
+        // A pinned rook slides along the pin but not off it
+        ("4k3/4r3/8/8/8/8/8/4RK2 b - - 0 1", 10),
+    ] {
+        let mut state = GameState::from_fen(fen).unwrap();
+        check_agree(&mut state, 1);
+        let mut moves = [Move(0); MAX_LEGAL_MOVES];
+        assert_eq!(state.generate_legal_moves(&mut moves), n, "{}", fen);
+    }
+}
+
+#[test]
+fn king_cannot_retreat_along_a_check() {
+    let mut state = GameState::from_fen("4k3/8/8/8/8/8/8/r3K3 w - - 0 1").unwrap();
+    let legality = state.legality();
+    assert_eq!(legality.checkers, A1.mask());
+    let mut moves = [Move(0); MAX_LEGAL_MOVES];
+    let n = state.generate_legal_moves(&mut moves);
+    let pacn: Vec<String> = moves[..n].iter().map(|mv| mv.pacn()).collect();
+    assert!(!pacn.contains(&String::from("e1f1")));
+    assert_eq!(n, 3);
+}
@@ -0,0 +1,105 @@
+use sublime::{
+    game::{
+        board_move::*,
+        move_gen::*,
+        position::*,
+    },
+    search::mate::*,
+};
+
+mod common;
+use common::*;
+
+
+fn solve(fen: &str, moves: u32, config: MateConfig) -> MateReport {
+    find_mate(&GameState::from_fen(fen).unwrap(), moves, config)
+}
+
+fn ends_in_mate(fen: &str, line: &[Move]) -> bool {
+    let mut state = GameState::from_fen(fen).unwrap();
+    for &mv in line {
+        state.push(mv);
+    }
+    let mut moves = [Move(0); MAX_LEGAL_MOVES];
+    state.generate_legal_moves(&mut moves) == 0 && state.in_check(state.turn())
+}
+
+
+#[test]
+fn mate_in_one() {
+    let report = solve(MATE_IN_ONE, 1, MateConfig::default());
+    let solution = report.solution.unwrap();
+    assert_eq!(solution.mv.pacn(), "a1a8");
+    assert!(solution.defences.is_empty());
+    assert!(report.cooks.is_empty());
+}
+
+#[test]
+fn solution_tree_covers_every_defence() {
+    let fen = MATE_IN_TWO;
+    assert_eq!(solve(fen, 1, MateConfig::default()).solution, None);
+    let solution = solve(fen, 2, MateConfig::default()).solution.unwrap();
+    assert_eq!(solution.mv.pacn(), "f6f7");
+    assert_eq!(solution.moves_to_mate(), 2);
+    // Kh7 is the only reply, and Rh1 mates
+    let variations = solution.variations();
+    assert_eq!(variations.len(), 1);
+    assert_eq!(variations[0].iter().map(|mv| mv.pacn()).collect::<Vec<_>>(), ["f6f7", "h8h7", "g1h1"]);
+
+    let fen = MATE_IN_THREE;
+    let solution = solve(fen, 3, MateConfig::default()).solution.unwrap();
+    assert_eq!(solution.moves_to_mate(), 3);
+    for line in solution.variations() {
+        assert!(line.len() % 2 == 1 && line.len() <= 5);
+        assert!(ends_in_mate(fen, &line));
+    }
+}
+
+#[test]
+fn checks_only_limits_the_attacker() {
+    // The key Kf7 is quiet
+    let report = solve(MATE_IN_TWO, 2, MateConfig { checks_only: true, ..MateConfig::default() });
+    assert_eq!(report.solution, None);
+    // Here every move of the mate is a check, and far fewer lines are tried
+    let fen = MATE_IN_THREE;
+    let full = solve(fen, 3, MateConfig::default());
+    let checks = solve(fen, 3, MateConfig { checks_only: true, ..MateConfig::default() });
+    assert_eq!(checks.solution, full.solution);
+    assert!(checks.nodes < full.nodes);
+}
+
+#[test]
+fn cooks_are_reported() {
+    // Ra8 and Re8 both mate
+    let fen = "6k1/5ppp/8/8/8/8/8/R3R1K1 w - - 0 1";
+    let report = solve(fen, 1, MateConfig { cooks: true, ..MateConfig::default() });
+    let mut keys: Vec<String> = report.solution.iter().chain(&report.cooks).map(|s| s.mv.pacn()).collect();
+    keys.sort();
+    assert_eq!(keys, ["a1a8", "e1e8"]);
+    assert!(solve(fen, 1, MateConfig::default()).cooks.is_empty());
+}
+
+#[test]
+fn stalemate_is_not_mate() {
+    // Qf7 leaves the king no move but gives no check; only Qg7 mates
+    let report = solve("7k/8/5KQ1/8/8/8/8/8 w - - 0 1", 1, MateConfig { cooks: true, ..MateConfig::default() });
+    assert_eq!(report.solution.unwrap().mv.pacn(), "g6g7");
+    assert!(report.cooks.is_empty());
+}
+
+#[test]
+fn longer_problems_stay_cheap() {
+    // Mate in 3 asked as mate in 4, cooks included: Kf5 and Kf6 also mate in time
+    let fen = "7k/8/8/6K1/8/8/8/6R1 w - - 0 1";
+    let report = solve(fen, 4, MateConfig { cooks: true, ..MateConfig::default() });
+    let solution = report.solution.unwrap();
+    assert_eq!((solution.mv.pacn().as_str(), solution.moves_to_mate()), ("g5g6", 3));
+    let mut cooks: Vec<String> = report.cooks.iter().map(|s| s.mv.pacn()).collect();
+    cooks.sort();
+    assert_eq!(cooks, ["g5f5", "g5f6"]);
+    for line in report.cooks.iter().flat_map(Solution::variations) {
+        assert!(line.len() <= 7 && ends_in_mate(fen, &line));
+    }
+    // Refuted positions are remembered rather than searched again at every depth
+    assert!(report.nodes < 50_000, "{} nodes", report.nodes);
+}
@@ -0,0 +1,58 @@
+use sublime::{
+    game::position::*,
+    search::{
+        *,
+        multipv::*,
+    },
+};
+
+mod common;
+use common::*;
+
+
+fn analyse_fen(fen: &str, lines: usize, depth: u64) -> Vec<PvLine> {
+    analyse(&GameState::from_fen(fen).unwrap(), lines, depth)
+}
+
+
+#[test]
+fn lines_are_distinct_and_ranked() {
+    let lines = analyse_fen(START_FEN, 4, 4);
+    assert_eq!(lines.len(), 4);
+    for (i, line) in lines.iter().enumerate() {
+        assert_eq!(line.depth, 4);
+        assert!(!line.pv.is_empty());
+        assert_eq!(line.pacn.len(), line.pv.len());
+        assert_eq!(line.san.len(), line.pv.len());
+        assert!(lines[i + 1..].iter().all(|other| other.best_move() != line.best_move()));
+        assert!(lines[i + 1..].iter().all(|other| other.score <= line.score));
+    }
+}
+
+#[test]
+fn best_line_matches_a_single_search() {
+    let lines = analyse_fen(WINS_QUEEN, 3, 3);
+    assert_eq!(lines[0].pacn[0], "d1d5");
+    assert_eq!(lines[0].san[0], "Rxd5");
+    // Anything else leaves the queen for the rook
+    assert!(lines[1].score < 0 && lines[2].score < 0);
+}
+
+#[test]
+fn notation_follows_the_line() {
+    let lines = analyse_fen(MATE_IN_ONE, 2, 3);
+    assert_eq!(lines[0].score(), Score::Mate(1));
+    assert_eq!(lines[0].san, ["Ra8#"]);
+    assert_eq!(lines[0].pacn, ["a1a8"]);
+    assert!(matches!(lines[1].score(), Score::Centipawns(_)));
+}
+
+#[test]
+fn no_more_lines_than_legal_moves() {
+    // Kxb2 is the only legal move
+    let lines = analyse_fen(ONLY_MOVE, 5, 3);
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0].san[0], "Kxb2");
+    // Checkmated, so there is nothing to analyse
+    assert!(analyse_fen(MATED, 3, 3).is_empty());
+}
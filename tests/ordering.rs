@@ -0,0 +1,89 @@
+use sublime::{
+    board::square::Square::*,
+    game::{
+        board_move::*,
+        move_gen::*,
+        position::*,
+    },
+    perft::FENS,
+    search::ordering::*,
+};
+
+
+fn picked(state: &mut GameState, picker: &mut MovePicker, history: &MoveHistory) -> Vec<Move> {
+    let mut moves = Vec::new();
+    while let Some(mv) = picker.next(state, history) {
+        moves.push(mv);
+    }
+    moves
+}
+
+fn compacts(moves: &[Move]) -> Vec<u16> {
+    let mut compacts: Vec<u16> = moves.iter().map(|mv| mv.compact()).collect();
+    compacts.sort();
+    compacts
+}
+
+
+#[test]
+fn picker_yields_each_legal_move_once() {
+    let history = MoveHistory::new();
+    for fen in FENS {
+        let mut state = GameState::from_fen(fen).unwrap();
+        let mut moves = [Move(0); MAX_LEGAL_MOVES];
+        let n = state.generate_legal_moves(&mut moves);
+        let expected = compacts(&moves[..n]);
+        // With no hints, and with every move in turn as the hash move and killer
+        let mut picker = MovePicker::new(&state, 0, [0, 0], 0);
+        assert_eq!(compacts(&picked(&mut state, &mut picker, &history)), expected, "{}", fen);
+        for (i, mv) in moves[..n].iter().enumerate() {
+            let other = moves[(i + 1) % n].compact();
+            let mut picker = MovePicker::new(&state, mv.compact(), [mv.compact(), other], other);
+            let yielded = picked(&mut state, &mut picker, &history);
+            assert_eq!(yielded[0], *mv);
+            assert_eq!(compacts(&yielded), expected, "{} {}", fen, mv.pacn());
+        }
+    }
+}
+
+#[test]
+fn illegal_hash_moves_are_ignored() {
+    let mut state = GameState::from_fen(START_FEN).unwrap();
+    let history = MoveHistory::new();
+    let illegal = Move::new(&state, E2, E5, None).compact();
+    let mut picker = MovePicker::new(&state, illegal, [illegal, 0], illegal);
+    assert_eq!(picked(&mut state, &mut picker, &history).len(), 20);
+}
+
+fn pacn(state: &mut GameState, hash_move: u16, killers: [u16; 2], history: &MoveHistory) -> Vec<String> {
+    let mut picker = MovePicker::new(state, hash_move, killers, 0);
+    picked(state, &mut picker, history).iter().map(|mv| mv.pacn()).collect()
+}
+
+
+#[test]
+fn losing_captures_come_last() {
+    // Nxd5 wins the exchange, Nxa4 and Qxa4 a pawn, and Qxd5 loses the queen for a rook
+    let mut state = GameState::from_fen("4k3/8/4p3/3r4/p7/2N5/8/3Q2K1 w - - 0 1").unwrap();
+    let history = MoveHistory::new();
+    let moves = pacn(&mut state, 0, [0, 0], &history);
+    assert_eq!(moves[..3], ["c3d5", "c3a4", "d1a4"]);
+    assert_eq!(moves.last().unwrap(), "d1d5");
+    // The hash move goes first whatever it is
+    let hash_move = Move::new(&state, D1, D5, None).compact();
+    assert_eq!(pacn(&mut state, hash_move, [0, 0], &history)[..3], ["d1d5", "c3d5", "c3a4"]);
+}
+
+#[test]
+fn killers_then_history_order_the_quiets() {
+    let mut state = GameState::from_fen("4k3/8/4p3/3r4/p7/2N5/8/3Q2K1 w - - 0 1").unwrap();
+    let mut history = MoveHistory::new();
+    let killer = Move::new(&state, G1, H1, None).compact();
+    assert_eq!(pacn(&mut state, 0, [killer, 0], &history)[3], "g1h1");
+    // A cutoff makes the move a killer and raises its history over the quiets tried before it
+    let tried = [Move::new(&state, D1, D4, None)];
+    history.update_quiet(Move::new(&state, C3, E4, None), None, 0, 8, &tried);
+    assert_eq!(history.killers(0)[0], Move::new(&state, C3, E4, None).compact());
+    assert!(history.history(tried[0]) < 0);
+    assert_eq!(pacn(&mut state, 0, [0, 0], &history)[3], "c3e4");
+}
@@ -0,0 +1,126 @@
+use {
+    std::sync::atomic::{AtomicBool, Ordering},
+    sublime::{
+        game::position::*,
+        perft::{*, divide::*, epd::*, hashed::*, parallel::*},
+    },
+};
+
+
+// Rows above this many nodes are only run by the ignored test
+const SHALLOW_NODE_LIMIT: i64 = 100_000;
+// Rows above this many nodes are impractical even for the ignored test
+const DEEP_NODE_LIMIT: i64 = 1_000_000_000;
+
+
+fn check_expected(min_nodes: i64, max_nodes: i64, threads: usize) {
+    let mut failures = Vec::new();
+    for (fen, depth, expected) in EXPECTED {
+        if expected[0] <= min_nodes || expected[0] > max_nodes {
+            continue;
+        }
+        let result = PerftResult::run_parallel(fen, depth, threads).unwrap();
+        if !result.counts.matches(&expected) {
+            failures.push(format!(
+                "{} depth {}\n  expected {:?}\n  actual   {:?}",
+                fen, depth, expected, result.counts.to_array()));
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+
+#[test]
+fn perft_shallow() {
+    check_expected(-1, SHALLOW_NODE_LIMIT, 1);
+}
+
+#[test]
+#[ignore]
+fn perft_deep() {
+    check_expected(SHALLOW_NODE_LIMIT, DEEP_NODE_LIMIT, default_threads());
+}
+
+#[test]
+fn parallel_matches_serial() {
+    for fen in [FENS[1], FENS[3]] {
+        let mut state = GameState::from_fen(fen).unwrap();
+        let serial = perft(&mut state, 3);
+        for threads in [2, 5] {
+            assert_eq!(parallel_perft(&state, 3, threads), serial, "{} threads on {}", threads, fen);
+        }
+    }
+}
+
+
+#[test]
+fn hashed_matches_serial() {
+    // A small cache forces both replacement policies to evict entries
+    for replacement in [Replacement::Always, Replacement::DepthPreferred] {
+        let mut cache = PerftCache::with_capacity(1 << 10, replacement);
+        for fen in [FENS[0], FENS[1], FENS[2]] {
+            let mut state = GameState::from_fen(fen).unwrap();
+            let serial = perft(&mut state, 3);
+            assert_eq!(hashed_perft(&mut state, 3, &mut cache), serial, "{}", fen);
+            assert_eq!(verified_hashed_perft(&mut state, 3, &mut cache).unwrap(), serial, "{}", fen);
+        }
+        assert!(cache.hits > 0);
+    }
+}
+
+
+#[test]
+fn epd_reports_malformed_lines() {
+    let text = "\
+r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - ;D1 48 ;D2 2039
+rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNX w KQkq - 0 1 ;D1 20
+
+4k3/8/8/8/8/8/8/4K2R w K - 0 1 ;D1 15 ;D2 x
+8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1 ;D1 14 ;D2 191 ;D3 2812
+";
+    let (cases, errors) = parse_epd(text);
+    assert_eq!(errors.iter().map(|e| e.line).collect::<Vec<_>>(), [2, 4]);
+    assert!(matches!(errors[0].error, EpdError::InvalidFen(_)));
+    assert!(matches!(errors[1].error, EpdError::MalformedDepth(_)));
+    assert_eq!(cases.len(), 5);
+    let results = run_epd(&cases, 2, 1, |_| ());
+    assert_eq!(results.len(), 4);
+    assert!(results.iter().all(|r| r.passed()));
+}
+
+
+fn listing(state: &mut GameState, depth: u64) -> String {
+    divide(state, depth).iter()
+        .map(|(mv, nodes)| format!("{}: {}\n", mv.pacn(), nodes))
+        .collect()
+}
+
+#[test]
+fn divide_sums_to_perft() {
+    let mut state = GameState::from_fen(FENS[1]).unwrap();
+    let total: i64 = divide(&mut state, 3).iter().map(|(_, n)| n).sum();
+    assert_eq!(total, 97_862);
+    // The stoppable version counts the same until stopped
+    let stop = AtomicBool::new(false);
+    assert_eq!(divide_until(&mut state, 3, &stop), Some(divide(&mut state, 3)));
+    stop.store(true, Ordering::Relaxed);
+    assert_eq!(divide_until(&mut state, 3, &stop), None);
+}
+
+#[test]
+fn divergence_descends_to_faulty_move() {
+    // Pretend the reference engine sees one more reply after e2e4 e7e5
+    let mut state = GameState::from_fen(FENS[0]).unwrap();
+    let reference = listing(&mut state, 3).replace("e2e4: 600", "e2e4: 601");
+    let divergence = find_divergence(&mut state, 3, &reference, |child, path, depth| {
+        let mut child = GameState::from_fen(&child.fen()).unwrap();
+        let s = listing(&mut child, depth);
+        match path.iter().map(|mv| mv.pacn()).collect::<Vec<_>>()[..] {
+            [ref a] if a == "e2e4" => s.replace("e7e5: 29", "e7e5: 30"),
+            [ref a, ref b] if a == "e2e4" && b == "e7e5" => s + "a1a8: 1\n",
+            _ => s,
+        }
+    }).unwrap();
+    assert_eq!(divergence.path.iter().map(|mv| mv.pacn()).collect::<Vec<_>>(), ["e2e4", "e7e5"]);
+    assert_eq!(divergence.mismatch, DivideMismatch::MissingMove(String::from("a1a8")));
+}
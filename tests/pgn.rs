@@ -0,0 +1,145 @@
+use sublime::{
+    game::{
+        board_move::*,
+        position::*,
+    },
+    parse::{
+        pgn::*,
+        san::*,
+    },
+};
+
+
+const GAMES: &str = r#"[Event "Casual"]
+[Site "?"]
+[Date "2024.01.01"]
+[Round "-"]
+[White "A \"Quoted\" Player"]
+[Black "B"]
+[Result "1-0"]
+
+{Opening comment} 1. e4 e5 2. Nf3 $1 Nc6 (2... d6 {Philidor} 3. d4 (3. Bc4)) 3.Bb5 a6?!
+; rest of line comment
+4. Ba4 Nf6 5. O-O 1-0
+
+%escaped line
+[Event "Second"]
+[Result "*"]
+[SetUp "1"]
+[FEN "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1"]
+
+1. e4 Kd7 2. e5 *
+[Event "Third"]
+
+1. e4 e5 2. Ke2 Ke7 3. Qh5 *
+"#;
+
+
+#[test]
+fn reads_multiple_games() {
+    let games = parse_pgn(GAMES);
+    assert_eq!(games.len(), 3);
+
+    let first = games[0].as_ref().unwrap();
+    assert_eq!(first.tag("White"), Some("A \"Quoted\" Player"));
+    assert_eq!(first.result, "1-0");
+    assert_eq!(first.mainline.comment.as_deref(), Some("Opening comment"));
+    let moves: Vec<String> = first.moves().iter().map(|mv| mv.pacn()).collect();
+    assert_eq!(moves, ["e2e4", "e7e5", "g1f3", "b8c6", "f1b5", "a7a6", "b5a4", "g8f6", "e1g1"]);
+    let nodes = &first.mainline.moves;
+    assert_eq!(nodes[2].nags, [1]);
+    assert_eq!(nodes[5].nags, [6]);
+    assert_eq!(nodes[5].comment.as_deref(), Some("rest of line comment"));
+    let variation = &nodes[3].variations[0];
+    assert_eq!(variation.moves[0].mv.pacn(), "d7d6");
+    assert_eq!(variation.moves[0].comment.as_deref(), Some("Philidor"));
+    assert_eq!(variation.moves[1].variations[0].moves[0].mv.pacn(), "f1c4");
+
+    let second = games[1].as_ref().unwrap();
+    assert_eq!(second.start.fen(), "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1");
+    assert_eq!(second.end().fen(), "8/3k4/8/4P3/8/8/8/4K3 b - - 0 2");
+    assert_eq!(second.result, "*");
+
+    let third = games[2].as_ref().unwrap_err();
+    assert_eq!((third.game, third.ply), (3, 4));
+    assert!(matches!(&third.error, PgnError::IllegalMove(san, SanError::NoMatchingMove) if san == "Qh5"));
+}
+
+#[test]
+fn reports_malformed_games() {
+    let games = parse_pgn("[Event \"x\"\n\n1. e4 *\n\n[FEN \"8/8 w\"]\n\n*\n\n1. e4 (1. d4 d5) e5 (1... c5 2. Nf3 *\n\n1. e4 {unterminated\n");
+    assert_eq!(games.len(), 4);
+    assert!(matches!(games[0], Err(PgnGameError { game: 1, ply: 0, error: PgnError::MalformedTag(_) })));
+    assert!(matches!(games[1], Err(PgnGameError { game: 2, ply: 0, error: PgnError::InvalidFen(_) })));
+    assert!(matches!(games[2], Err(PgnGameError { game: 3, ply: 3, error: PgnError::UnexpectedToken(_) })));
+    assert!(matches!(games[3], Err(PgnGameError { game: 4, ply: 1, error: PgnError::UnterminatedComment })));
+}
+
+#[test]
+fn start_position_by_default() {
+    let games = parse_pgn("1. d4 d5 *");
+    let game = games[0].as_ref().unwrap();
+    assert_eq!(game.start.fen(), START_FEN);
+    assert_eq!(game.moves().len(), 2);
+}
+
+#[test]
+fn writes_export_format() {
+    let mut game = parse_pgn(GAMES).remove(0).unwrap();
+    game.tags.push((String::from("Annotator"), String::from("Back\\slash")));
+    let pgn = game.to_pgn();
+    assert_eq!(pgn, r#"[Event "Casual"]
+[Site "?"]
+[Date "2024.01.01"]
+[Round "-"]
+[White "A \"Quoted\" Player"]
+[Black "B"]
+[Result "1-0"]
+[Annotator "Back\\slash"]
+
+{Opening comment} 1. e4 e5 2. Nf3 $1 Nc6 (2... d6 {Philidor} 3. d4 (3. Bc4)) 3.
+Bb5 a6 $6 {rest of line comment} 4. Ba4 Nf6 5. O-O 1-0
+
+"#);
+    assert_eq!(parse_pgn(&pgn).remove(0).unwrap(), game);
+}
+
+#[test]
+fn writes_set_up_positions() {
+    let state = GameState::from_fen("4k3/8/8/8/8/8/4P3/4K3 b - - 0 1").unwrap();
+    let moves = [
+        Move::from_san(&state, "Kd7").unwrap(),
+    ];
+    let game = PgnGame::new(state, &moves);
+    assert_eq!(game.to_pgn(), r#"[Event "?"]
+[Site "?"]
+[Date "????.??.??"]
+[Round "?"]
+[White "?"]
+[Black "?"]
+[Result "*"]
+[SetUp "1"]
+[FEN "4k3/8/8/8/8/8/4P3/4K3 b - - 0 1"]
+
+1... Kd7 *
+
+"#);
+}
+
+#[test]
+fn wraps_long_games() {
+    let mut moves = Vec::new();
+    let mut state = GameState::from_fen(START_FEN).unwrap();
+    for _ in 0..10 {
+        for san in ["Nf3", "Nf6", "Ng1", "Ng8"] {
+            let mv = Move::from_san(&state, san).unwrap();
+            state.push(mv);
+            moves.push(mv);
+        }
+    }
+    let game = PgnGame::new(GameState::from_fen(START_FEN).unwrap(), &moves);
+    let pgn = game.to_pgn();
+    assert!(pgn.lines().all(|line| line.len() <= MAX_LINE_WIDTH));
+    assert!(pgn.lines().filter(|line| line.contains("Nf3")).count() > 1);
+    assert_eq!(parse_pgn(&pgn).remove(0).unwrap().moves(), moves);
+}
@@ -0,0 +1,100 @@
+use sublime::{
+    board::{
+        piece::*,
+        square::{*, Square::*},
+    },
+    game::{
+        board_move::*,
+        move_gen::*,
+        position::*,
+    },
+    parse::pacn::*,
+    perft::FENS,
+};
+
+mod common;
+use common::*;
+
+
+#[test]
+fn push_pop_round_trips() {
+    for fen in FENS {
+        // Every move up to three plies deep
+        for_each_position(&mut GameState::from_fen(fen).unwrap(), 2, &mut |state| for mv in legal_moves(state) {
+            let before = state.clone();
+            let fen = state.fen();
+            let undo = state.push(mv);
+            assert_eq!(state.zobrist_hash, state.compute_zobrist(), "{} after {}", fen, mv.pacn());
+            state.pop(mv, undo);
+            assert_eq!(*state, before, "{} after {}", fen, mv.pacn());
+            assert_eq!(state.fen(), fen);
+        });
+    }
+}
+
+#[test]
+fn null_move_passes_the_turn() {
+    let mut state = GameState::from_fen("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3").unwrap();
+    let before = state.clone();
+    let undo = state.push_null();
+    assert_eq!(state.fen(), "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR b KQkq - 1 3");
+    assert_eq!(state.zobrist_hash, state.compute_zobrist());
+    state.pop_null(undo);
+    assert_eq!(state, before);
+}
+
+#[test]
+fn try_move_agrees_with_generator() {
+    for fen in FENS {
+        let mut state = GameState::from_fen(fen).unwrap();
+        let mut moves = [Move(0); MAX_LEGAL_MOVES];
+        let n = state.generate_legal_moves(&mut moves);
+        let mut found = 0;
+        for origin in Square::ALL {
+            for destination in Square::ALL {
+                for promotion in [None, Some(Promotion::Queen), Some(Promotion::Knight)] {
+                    if let Ok(mv) = state.try_move(origin, destination, promotion) {
+                        assert!(moves[..n].contains(&mv), "{} {}", fen, mv.pacn());
+                        found += 1;
+                    }
+                }
+            }
+        }
+        let underpromotions = moves[..n].iter()
+            .filter(|mv| matches!(mv.promotion(), Some(Promotion::Rook | Promotion::Bishop)))
+            .count();
+        assert_eq!(found + underpromotions, n, "{}", fen);
+    }
+}
+
+#[test]
+fn try_move_classifies_illegal_moves() {
+    let cases = [
+        ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", E3, E4, IllegalMove::EmptySquareMove),
+        ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", E7, E5, IllegalMove::OpponentPieceMove),
+        ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", D1, D2, IllegalMove::AlliedCapture),
+        ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", E2, E5, IllegalMove::InvalidMove),
+        ("4k3/8/8/8/8/8/8/4K2R w - - 0 1", E1, G1, IllegalMove::InvalidMove),
+        ("4k3/8/8/8/8/8/8/4KB1R w K - 0 1", E1, G1, IllegalMove::InvalidMove),
+        ("4r1k1/8/8/8/8/8/8/4K2R w K - 0 1", E1, G1, IllegalMove::CastleOutOfCheck),
+        ("5rk1/8/8/8/8/8/8/4K2R w K - 0 1", E1, G1, IllegalMove::CastleThroughCheck),
+        ("4k3/8/8/8/8/8/8/r3K2R w K - 0 1", E1, F1, IllegalMove::InCheck),
+        ("4k3/4r3/8/8/8/8/4B3/4K3 w - - 0 1", E2, D3, IllegalMove::InCheck),
+    ];
+    for (fen, origin, destination, error) in cases {
+        let state = GameState::from_fen(fen).unwrap();
+        assert_eq!(state.try_move(origin, destination, None), Err(error), "{}", fen);
+    }
+}
+
+#[test]
+fn pacn_rejects_illegal_moves() {
+    let state = GameState::from_fen("k7/4P3/8/8/8/8/8/K7 w - - 0 1").unwrap();
+    assert!(matches!(Move::from_str(&state, "e7e8"),
+        Err(PacnError::IllegalMove(IllegalMove::InvalidPromotion))));
+    assert!(matches!(Move::from_str(&state, "a1a2q"),
+        Err(PacnError::IllegalMove(IllegalMove::InvalidPromotion))));
+    assert!(matches!(Move::from_str(&state, "a1b3"),
+        Err(PacnError::IllegalMove(IllegalMove::InvalidMove))));
+    assert_eq!(Move::from_str(&state, "e7e8q").unwrap().pacn(), "e7e8q");
+}
@@ -0,0 +1,69 @@
+use sublime::{
+    game::{
+        board_move::*,
+        position::*,
+    },
+    parse::san::*,
+    perft::FENS,
+};
+
+mod common;
+use common::*;
+
+
+fn san(fen: &str, pacn: &str) -> String {
+    let state = GameState::from_fen(fen).unwrap();
+    Move::from_str(&state, pacn).unwrap().san(&state)
+}
+
+fn pacn(fen: &str, san: &str) -> Result<String, SanError> {
+    let state = GameState::from_fen(fen).unwrap();
+    Move::from_san(&state, san).map(|mv| mv.pacn())
+}
+
+
+#[test]
+fn san_round_trips() {
+    for fen in FENS {
+        for_each_position(&mut GameState::from_fen(fen).unwrap(), 1, &mut |state| for mv in legal_moves(state) {
+            let san = mv.san(state);
+            assert_eq!(Move::from_san(state, &san).unwrap(), mv, "{} {}", state.fen(), san);
+        });
+    }
+}
+
+#[test]
+fn san_generation() {
+    let kiwipete = FENS[1];
+    assert_eq!(san(kiwipete, "e1g1"), "O-O");
+    assert_eq!(san(kiwipete, "e1c1"), "O-O-O");
+    assert_eq!(san(kiwipete, "e5f7"), "Nxf7");
+    assert_eq!(san(kiwipete, "d5e6"), "dxe6");
+    assert_eq!(san(kiwipete, "c3b1"), "Nb1");
+    assert_eq!(san(kiwipete, "a1b1"), "Rb1");
+    // Knights on b1 and b5 can both reach c3 and a3, and a knight on d1 reaches c3
+    let files = "4k3/8/8/1N6/8/8/8/1N1NK3 w - - 0 1";
+    assert_eq!(san(files, "b5a3"), "N5a3");
+    assert_eq!(san(files, "b1c3"), "Nb1c3");
+    assert_eq!(san(files, "d1c3"), "Ndc3");
+    assert_eq!(san("k7/4P3/8/8/8/8/8/K7 w - - 0 1", "e7e8q"), "e8=Q+");
+    assert_eq!(san("k7/4P3/8/8/8/8/8/K7 w - - 0 1", "e7e8n"), "e8=N");
+    assert_eq!(san("rnbqkbnr/ppppp2p/5p2/6p1/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 3", "d1h5"), "Qh5#");
+}
+
+#[test]
+fn san_parsing_is_tolerant() {
+    let kiwipete = FENS[1];
+    assert_eq!(pacn(kiwipete, "0-0").unwrap(), "e1g1");
+    assert_eq!(pacn(kiwipete, "O-O-O!?").unwrap(), "e1c1");
+    assert_eq!(pacn(kiwipete, "Nf7").unwrap(), "e5f7");
+    assert_eq!(pacn(kiwipete, "de6").unwrap(), "d5e6");
+    assert_eq!(pacn(kiwipete, "Qxf6+?!").unwrap(), "f3f6");
+    assert_eq!(pacn("k7/4P3/8/8/8/8/8/K7 w - - 0 1", "e8Q").unwrap(), "e7e8q");
+    assert_eq!(pacn("k7/4P3/8/8/8/8/8/K7 w - - 0 1", "e8=R").unwrap(), "e7e8r");
+    assert!(matches!(pacn("4k3/8/8/1N6/8/8/8/1N1NK3 w - - 0 1", "Nc3"), Err(SanError::AmbiguousMove)));
+    assert!(matches!(pacn(kiwipete, "Ke3"), Err(SanError::NoMatchingMove)));
+    assert!(matches!(pacn(kiwipete, "Zf3"), Err(SanError::MalformedSan)));
+    assert!(matches!(pacn(kiwipete, "Nccd3"), Err(SanError::MalformedSan)));
+    assert!(matches!(pacn(kiwipete, "e9"), Err(SanError::ConversionError(_))));
+}
@@ -0,0 +1,122 @@
+use {
+    std::time::Duration,
+    sublime::{
+        game::{
+            board_move::*,
+            position::*,
+        },
+        perft::FENS,
+        search::*,
+    },
+};
+
+mod common;
+use common::*;
+
+
+fn search(fen: &str, limits: SearchLimits) -> SearchInfo {
+    Searcher::new().search(&GameState::from_fen(fen).unwrap(), &[], limits, |_| ())
+}
+
+
+#[test]
+fn finds_mate_in_one() {
+    let info = search(MATE_IN_ONE, SearchLimits::depth(4));
+    assert_eq!(info.best_move().unwrap().pacn(), "a1a8");
+    assert_eq!(info.score(), Score::Mate(1));
+    // Search ends early once the mate cannot be bettered
+    assert_eq!(info.depth, 1);
+}
+
+#[test]
+fn finds_mate_in_two() {
+    let info = search(MATE_IN_TWO, SearchLimits::depth(5));
+    assert_eq!(info.score(), Score::Mate(2));
+    assert_eq!(info.pv.len(), 3);
+}
+
+#[test]
+fn reports_being_mated() {
+    // Only move Kg8, then Ra8 mates
+    let info = search("7k/R7/6K1/8/8/8/8/8 b - - 0 1", SearchLimits::depth(3));
+    assert_eq!(info.score(), Score::Mate(-1));
+    let info = search(MATED, SearchLimits::depth(3));
+    assert_eq!(info.score(), Score::Mate(0));
+    assert_eq!(info.best_move(), None);
+}
+
+#[test]
+fn wins_material() {
+    let state = GameState::from_fen(WINS_QUEEN).unwrap();
+    assert_eq!(best_move(&state, 3).unwrap().pacn(), "d1d5");
+}
+
+#[test]
+fn iterations_are_reported() {
+    let state = GameState::from_fen(START_FEN).unwrap();
+    let mut depths = Vec::new();
+    let info = Searcher::new().search(&state, &[], SearchLimits::depth(4), |info| depths.push(info.depth));
+    assert_eq!(depths, [1, 2, 3, 4]);
+    assert!(info.nodes > 0);
+    assert!(!info.pv.is_empty());
+}
+
+#[test]
+fn limits_end_the_search() {
+    let info = search(START_FEN, SearchLimits { nodes: Some(5_000), ..SearchLimits::default() });
+    assert!(info.nodes < 6_000 && info.best_move().is_some());
+    let info = search(START_FEN, SearchLimits { time: Some(Duration::from_millis(50)), ..SearchLimits::default() });
+    assert!(info.elapsed < Duration::from_millis(1000) && info.best_move().is_some());
+
+    let mut searcher = Searcher::new();
+    searcher.stop_flag().store(true, std::sync::atomic::Ordering::Relaxed);
+    let info = searcher.search(&GameState::from_fen(START_FEN).unwrap(), &[], SearchLimits::default(), |_| ());
+    // The first iteration still completes
+    assert_eq!(info.depth, 1);
+}
+
+#[test]
+fn repetition_is_a_draw() {
+    // A lone king heads back to a position from the game history rather than lose on material
+    let mut state = GameState::from_fen("7k/8/8/8/8/8/8/1Q4K1 w - - 0 1").unwrap();
+    let mut history = Vec::new();
+    for pacn in ["b1c1", "h8g8", "c1b1"] {
+        history.push(state.zobrist_hash);
+        let mv = Move::from_str(&state, pacn).unwrap();
+        state.push(mv);
+    }
+    let info = Searcher::new().search(&state, &history, SearchLimits::depth(3), |_| ());
+    assert_eq!(info.score, DRAW);
+    assert_eq!(info.best_move().unwrap().pacn(), "g8h8");
+}
+
+#[test]
+fn selectivity_saves_nodes() {
+    let state = GameState::from_fen(FENS[1]).unwrap();
+    let nodes = |config| {
+        let mut searcher = Searcher::new();
+        searcher.set_config(config);
+        searcher.search(&state, &[], SearchLimits::depth(5), |_| ()).nodes
+    };
+    assert!(nodes(SearchConfig::default()) < nodes(SearchConfig::PLAIN) / 2);
+}
+
+#[test]
+fn tactics_survive_each_technique() {
+    let plain = SearchConfig::PLAIN;
+    for config in [
+        SearchConfig::default(),
+        SearchConfig { null_move: true, ..plain },
+        SearchConfig { late_move_reductions: true, ..plain },
+        SearchConfig { futility: true, ..plain },
+        SearchConfig { reverse_futility: true, ..plain },
+        SearchConfig { razoring: true, ..plain },
+        SearchConfig { check_extensions: true, ..plain },
+    ] {
+        let mut searcher = Searcher::new();
+        searcher.set_config(config);
+        let mut search = |fen| searcher.search(&GameState::from_fen(fen).unwrap(), &[], SearchLimits::depth(5), |_| ());
+        assert_eq!(search(MATE_IN_TWO).score(), Score::Mate(2), "{:?}", config);
+        assert_eq!(search(WINS_QUEEN).best_move().unwrap().pacn(), "d1d5", "{:?}", config);
+    }
+}
@@ -0,0 +1,68 @@
+use sublime::{
+    board::{
+        piece::*,
+        square::Square::{self, *},
+    },
+    game::position::*,
+    search::*,
+};
+
+
+fn see(fen: &str, origin: Square, destination: Square) -> i32 {
+    let state = GameState::from_fen(fen).unwrap();
+    state.see(state.try_move(origin, destination, None).unwrap())
+}
+
+
+#[test]
+fn undefended_capture_wins_the_victim() {
+    assert_eq!(see("1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1", E1, E5), 100);
+}
+
+#[test]
+fn exchange_stops_when_behind() {
+    // Nxe5 Nxe5 Rxe5 Bxe5 and white should not have started
+    assert_eq!(see("1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1", D3, E5), -200);
+}
+
+#[test]
+fn xray_attackers_join_in() {
+    // The rook on d1 backs up the one on d2
+    assert_eq!(see("3rk3/8/8/3p4/8/8/3R4/3RK3 w - - 0 1", D2, D5), 100);
+    assert_eq!(see("3rk3/8/8/3p4/8/8/3R4/4K3 w - - 0 1", D2, D5), -400);
+    // The queen behind the bishop gets a second pawn for it
+    assert_eq!(see("4k3/8/5p2/4p3/8/8/1B4K1/Q7 w - - 0 1", B2, E5), -100);
+}
+
+#[test]
+fn quiet_moves_onto_attacked_squares() {
+    let fen = "4k3/8/4p3/8/8/8/8/3QK3 w - - 0 1";
+    assert_eq!(see(fen, D1, D5), -900);
+    assert_eq!(see(fen, D1, D4), 0);
+}
+
+#[test]
+fn king_cannot_recapture_on_a_defended_square() {
+    assert_eq!(see("4k3/8/8/8/8/4r3/3r4/4K3 b - - 0 1", E3, E2), 0);
+    assert_eq!(see("4k3/8/8/8/8/4r3/8/4K3 b - - 0 1", E3, E2), -500);
+    assert_eq!(see("4k3/8/8/8/8/8/4q3/3RK3 w - - 0 1", E1, E2), 900);
+}
+
+#[test]
+fn promotions_and_en_passant() {
+    let state = GameState::from_fen("3r3k/4P3/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    let mv = state.try_move(E7, D8, Some(Promotion::Queen)).unwrap();
+    assert_eq!(state.see(mv), 500 + 800);
+    assert!(state.see_ge(mv, 1300));
+    assert!(!state.see_ge(mv, 1301));
+    let mv = state.try_move(E7, E8, Some(Promotion::Queen)).unwrap();
+    assert_eq!(state.see(mv), -100);
+    assert_eq!(see("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", E5, D6), 100);
+}
+
+#[test]
+fn quiescence_sees_the_recapture() {
+    // Qxd5 wins a pawn at depth 1 unless the recapture is looked at
+    let state = GameState::from_fen("4k3/8/4p3/3p4/8/8/8/3QK3 w - - 0 1").unwrap();
+    assert_ne!(best_move(&state, 1).unwrap().pacn(), "d1d5");
+}
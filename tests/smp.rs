@@ -0,0 +1,94 @@
+use {
+    std::{
+        thread,
+        time::Duration,
+    },
+    sublime::{
+        game::position::*,
+        search::{
+            *,
+            tt::*,
+        },
+    },
+};
+
+mod common;
+use common::*;
+
+
+fn search_with_threads(fen: &str, threads: usize, limits: SearchLimits) -> SearchInfo {
+    let mut searcher = Searcher::new();
+    searcher.set_threads(threads);
+    searcher.search(&GameState::from_fen(fen).unwrap(), &[], limits, |_| ())
+}
+
+
+#[test]
+fn single_thread_is_deterministic() {
+    let fen = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3";
+    let first = search_with_threads(fen, 1, SearchLimits::depth(6));
+    let second = search_with_threads(fen, 1, SearchLimits::depth(6));
+    assert_eq!((first.score, first.nodes, first.pv), (second.score, second.nodes, second.pv));
+}
+
+#[test]
+fn helpers_share_the_work() {
+    let info = search_with_threads(WINS_QUEEN, 4, SearchLimits::depth(6));
+    assert_eq!(info.best_move().unwrap().pacn(), "d1d5");
+    let info = search_with_threads(MATE_IN_TWO, 4, SearchLimits::depth(5));
+    assert_eq!(info.score(), Score::Mate(2));
+
+    // Helpers stop with the main thread
+    let limits = SearchLimits { time: Some(Duration::from_millis(50)), ..SearchLimits::default() };
+    let info = search_with_threads(START_FEN, 3, limits);
+    assert!(info.elapsed < Duration::from_millis(1000) && info.best_move().is_some());
+}
+
+#[test]
+fn helpers_keep_the_pv_whole() {
+    // Deeper entries stored by helpers must not cut the main thread's PV short.
+    // A deeper search beforehand leaves such entries however the threads are scheduled.
+    let state = GameState::from_fen(START_FEN).unwrap();
+    let mut searcher = Searcher::new();
+    searcher.search(&state, &[], SearchLimits::depth(7), |_| ());
+    searcher.set_threads(4);
+    let mut depths = Vec::new();
+    searcher.search(&state, &[], SearchLimits::depth(6), |info| depths.push((info.depth, info.pv.len())));
+    for (depth, length) in depths.into_iter().filter(|&(depth, _)| depth >= 3) {
+        assert!(length > 1, "depth {} pv has {} moves", depth, length);
+    }
+}
+
+#[test]
+fn node_counts_include_helpers() {
+    let info = search_with_threads(START_FEN, 4, SearchLimits::depth(6));
+    assert_eq!(info.thread_nodes.len(), 4);
+    assert_eq!(info.nodes, info.thread_nodes.iter().sum::<u64>());
+    assert!(info.nodes > info.thread_nodes[0]);
+    // Node limits apply to all threads together
+    let limits = SearchLimits { nodes: Some(20_000), ..SearchLimits::default() };
+    let info = search_with_threads(START_FEN, 4, limits);
+    assert_eq!(info.nodes, info.thread_nodes.iter().sum::<u64>());
+    assert!(info.nodes >= 20_000 && info.best_move().is_some());
+}
+
+#[test]
+fn concurrent_entries_verify() {
+    // Writers race on a small table; a torn slot must never be returned as an entry
+    let tt = TranspositionTable::with_capacity(64);
+    let key_of = |i: u64| i.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    thread::scope(|scope| {
+        for t in 0..4u64 {
+            let tt = &tt;
+            scope.spawn(move || for i in 0..20_000u64 {
+                let n = i * 4 + t;
+                tt.store(key_of(n), 0, (n % 64) as u8, Bound::Exact, (n % 1000) as i32, None);
+                let probe = key_of(i * 4 + (t + 1) % 4);
+                if let Some(entry) = tt.probe(probe, 0) {
+                    let m = i * 4 + (t + 1) % 4;
+                    assert_eq!((entry.depth, entry.score), ((m % 64) as u8, (m % 1000) as i32));
+                }
+            });
+        }
+    });
+}
@@ -0,0 +1,55 @@
+use sublime::{
+    game::{
+        board_move::*,
+        position::*,
+    },
+    perft::FENS,
+};
+
+mod common;
+use common::*;
+
+
+fn check_staged(state: &mut GameState) {
+    let legal = generated(state, GameState::generate_legal_moves);
+    let fen = state.fen();
+    if state.in_check(state.turn()) {
+        assert_eq!(generated(state, GameState::generate_evasions), legal, "{}", fen);
+    }
+    let captures = generated(state, GameState::generate_captures);
+    let quiets = generated(state, GameState::generate_quiets);
+    let mut union = [captures.clone(), quiets.clone()].concat();
+    union.sort();
+    assert_eq!(union, legal, "{}", fen);
+    assert!(captures.iter().all(|&mv| Move(mv).is_capture() || Move(mv).is_promotion()), "{}", fen);
+
+    // Quiet checks are exactly the quiets after which the opponent is in check
+    let checks: Vec<u64> = quiets.iter().copied().filter(|&mv| {
+        let undo = state.push(Move(mv));
+        let check = state.in_check(state.turn());
+        state.pop(Move(mv), undo);
+        check
+    }).collect();
+    assert_eq!(generated(state, GameState::generate_quiet_checks), checks, "{}", fen);
+}
+
+
+#[test]
+fn staged_generators_partition_the_legal_moves() {
+    for fen in FENS {
+        for_each_position(&mut GameState::from_fen(fen).unwrap(), 2, &mut check_staged);
+    }
+}
+
+#[test]
+fn tricky_positions() {
+    for fen in [
+        "4k3/8/8/8/8/8/3n4/R3K2R w KQ - 0 1",  // Knight check: no blocks, no castling
+        "4k3/8/8/1b6/8/8/8/R3K2R w KQ - 0 1",  // Bishop check from a distance
+        "4k3/8/8/8/8/5n2/8/4K2r w - - 0 1",  // Double check
+        "8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1",  // En passant removes the checker
+        "8/8/8/8/k2Pp2Q/8/8/4K3 b - d3 0 1",  // En passant that would expose the king along the rank
+    ] {
+        check_staged(&mut GameState::from_fen(fen).unwrap());
+    }
+}
@@ -0,0 +1,100 @@
+use {
+    std::{
+        sync::Arc,
+        time::Duration,
+    },
+    sublime::{
+        game::{
+            board_move::*,
+            position::*,
+        },
+        search::{
+            *,
+            time::*,
+        },
+    },
+};
+
+mod common;
+use common::*;
+
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+fn managed_search(fen: &str, clock: Arc<dyn Clock>, limits: ClockLimits) -> SearchInfo {
+    let mut searcher = Searcher::new();
+    searcher.set_clock(clock);
+    let limits = SearchLimits { clock: Some(limits), ..SearchLimits::default() };
+    searcher.search(&GameState::from_fen(fen).unwrap(), &[], limits, |_| ())
+}
+
+
+#[test]
+fn deadlines_follow_the_clock() {
+    // 9970 ms after overhead, spread over 25 moves
+    let tm = TimeManager::new(&ClockLimits::new(ms(10_000), ms(0), None));
+    assert_eq!(tm.soft_deadline(), Duration::from_micros(398_800));
+    assert_eq!(tm.hard_deadline(), Duration::from_micros(1_595_200));
+
+    // Most of the increment is spent on top
+    let tm = TimeManager::new(&ClockLimits::new(ms(10_000), ms(1000), None));
+    assert_eq!(tm.soft_deadline(), Duration::from_micros(1_148_800));
+
+    // The last move before the control keeps a reserve
+    let tm = TimeManager::new(&ClockLimits::new(ms(10_000), ms(0), Some(1)));
+    assert_eq!(tm.soft_deadline(), ms(4985));
+    assert_eq!(tm.hard_deadline(), ms(7976));
+}
+
+#[test]
+fn overhead_is_set_aside() {
+    let limits = ClockLimits { overhead: ms(1000), ..ClockLimits::new(ms(10_000), ms(0), Some(1)) };
+    assert_eq!(TimeManager::new(&limits).soft_deadline(), ms(4500));
+    let limits = ClockLimits { overhead: ms(1000), ..ClockLimits::new(ms(500), ms(0), None) };
+    assert_eq!(TimeManager::new(&limits).hard_deadline(), Duration::ZERO);
+}
+
+#[test]
+fn instability_and_score_drops_extend_the_search() {
+    let state = GameState::from_fen(START_FEN).unwrap();
+    let (e4, d4) = (Move::from_str(&state, "e2e4").unwrap(), Move::from_str(&state, "d2d4").unwrap());
+    let mut tm = TimeManager::new(&ClockLimits::new(ms(10_000), ms(0), None));
+    let soft = tm.soft_deadline();
+
+    tm.iteration_done(Some(e4), 30);
+    tm.iteration_done(Some(e4), 30);
+    assert_eq!(tm.soft_deadline(), soft);
+    tm.iteration_done(Some(d4), 30);
+    assert_eq!(tm.soft_deadline(), soft.mul_f64(1.5));
+    // Instability fades once the best move settles
+    tm.iteration_done(Some(d4), 30);
+    assert_eq!(tm.soft_deadline(), soft.mul_f64(1.25));
+
+    tm.iteration_done(Some(d4), -70);
+    assert_eq!(tm.soft_deadline(), soft.mul_f64(1.125 * 1.6));
+    assert!(!tm.should_stop(soft));
+    assert!(tm.should_stop(tm.hard_deadline()));
+}
+
+#[test]
+fn single_legal_move_is_played_at_once() {
+    // Kxb2 is forced; the clock never moves, so only the single move can end the search
+    let info = managed_search(ONLY_MOVE, Arc::new(ManualClock::new()),
+        ClockLimits::new(ms(60_000), ms(0), None));
+    assert_eq!(info.depth, 1);
+    assert_eq!(info.best_move().unwrap().pacn(), "a1b2");
+}
+
+#[test]
+fn managed_search_respects_the_hard_deadline() {
+    // Every reading of the clock advances it, so the search is cut off deterministically
+    let limits = ClockLimits::new(ms(2000), ms(0), None);
+    let hard = TimeManager::new(&limits).hard_deadline();
+    let first = managed_search(START_FEN, Arc::new(ManualClock::ticking(ms(1))), limits);
+    let second = managed_search(START_FEN, Arc::new(ManualClock::ticking(ms(1))), limits);
+    assert!(first.best_move().is_some());
+    assert!(first.elapsed <= hard + ms(2));
+    assert_eq!((first.depth, first.nodes, first.pv), (second.depth, second.nodes, second.pv));
+}
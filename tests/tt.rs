@@ -0,0 +1,90 @@
+use sublime::{
+    game::{
+        board_move::*,
+        position::*,
+    },
+    search::{
+        *,
+        tt::*,
+    },
+};
+
+
+#[test]
+fn mate_scores_are_relative_to_the_node() {
+    // Mate in 3 plies from the root, found at ply 1, is 2 plies from that node
+    let root_score = MATE - 3;
+    let stored = score_to_tt(root_score, 1);
+    assert_eq!(stored, MATE - 2);
+    // Reached again at ply 5 the same node is mate 7 plies from the root
+    assert_eq!(score_from_tt(stored, 5), MATE - 7);
+    assert_eq!(score_from_tt(score_to_tt(-MATE + 4, 2), 6), -MATE + 8);
+    // Ordinary scores are untouched
+    assert_eq!(score_to_tt(150, 9), 150);
+    assert_eq!(score_from_tt(-150, 9), -150);
+}
+
+#[test]
+fn mate_scores_round_trip_through_the_table() {
+    let tt = TranspositionTable::with_capacity(1024);
+    let key = 0x1234_5678_9abc_def0;
+    tt.store(key, 3, 5, Bound::Exact, -MATE + 10, None);
+    let entry = tt.probe(key, 3).unwrap();
+    assert_eq!(entry.score, -MATE + 10);
+    assert_eq!(tt.probe(key, 1).unwrap().score, -MATE + 8);
+    assert_eq!(Score::from_value(tt.probe(key, 0).unwrap().score), Score::Mate(-4));
+}
+
+#[test]
+fn entries_are_verified_and_replaced() {
+    let state = GameState::from_fen(START_FEN).unwrap();
+    let mv = Move::from_str(&state, "e2e4").unwrap();
+    let tt = TranspositionTable::with_capacity(1000);
+    assert_eq!(tt.capacity(), 512);
+
+    let key = 42;
+    tt.store(key, 0, 6, Bound::Lower, 35, Some(mv));
+    let entry = tt.probe(key, 0).unwrap();
+    assert_eq!((entry.depth, entry.bound, entry.score), (6, Bound::Lower, 35));
+    assert_eq!(entry.best_move, mv.compact());
+    // Same slot, different verification key
+    assert_eq!(tt.probe(key + 512, 0), None);
+
+    // A shallower entry for another position does not evict a deeper one
+    tt.store(key + 512, 0, 2, Bound::Exact, 0, None);
+    assert!(tt.probe(key, 0).is_some());
+    // Unless the deeper one is from an earlier search
+    tt.new_search();
+    tt.store(key + 512, 0, 2, Bound::Exact, 0, None);
+    assert_eq!(tt.probe(key, 0), None);
+    // Failing low without a move keeps the move already known for the position
+    tt.store(key + 512, 0, 3, Bound::Upper, -20, Some(mv));
+    tt.store(key + 512, 0, 4, Bound::Upper, -30, None);
+    assert_eq!(tt.probe(key + 512, 0).unwrap().best_move, mv.compact());
+
+    tt.clear();
+    assert_eq!(tt.probe(key + 512, 0), None);
+}
+
+#[test]
+fn hashfull_counts_current_entries() {
+    let tt = TranspositionTable::with_capacity(2048);
+    assert_eq!(tt.hashfull(), 0);
+    for key in 0..500 {
+        tt.store(key, 0, 1, Bound::Exact, 0, None);
+    }
+    assert_eq!(tt.hashfull(), 500);
+    tt.new_search();
+    assert_eq!(tt.hashfull(), 0);
+    assert_eq!(TranspositionTable::new(1).megabytes(), 1);
+}
+
+#[test]
+fn search_fills_the_table() {
+    let mut searcher = Searcher::new();
+    let state = GameState::from_fen(START_FEN).unwrap();
+    let info = searcher.search(&state, &[], SearchLimits::depth(4), |_| ());
+    let entry = searcher.tt().probe(state.zobrist_hash, 0).unwrap();
+    assert_eq!(entry.best_move, info.best_move().unwrap().compact());
+    assert_eq!(entry.score, info.score);
+}